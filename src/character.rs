@@ -1,16 +1,29 @@
 pub mod camera;
 pub mod config;
-pub mod crouch;
+pub mod dash;
 pub mod jump;
+pub mod kinematic;
 mod movement;
+pub mod platform_carry;
 mod rotation;
+pub mod slide;
 pub mod spawner;
+pub mod stamina;
+pub mod stance;
+pub mod step_up;
+pub mod wall_run;
+
+use std::f32::consts::{PI, TAU};
 
 use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
 
 use self::{
-    crouch::CharacterCrouchPlugin, jump::CharacterJumpPlugin, movement::CharacterMovementPlugin,
-    rotation::CharacterRotationPlugin,
+    dash::CharacterDashPlugin, jump::CharacterJumpPlugin, kinematic::CharacterKinematicPlugin,
+    movement::CharacterMovementPlugin, platform_carry::CharacterPlatformCarryPlugin,
+    rotation::CharacterRotationPlugin, slide::CharacterSlidePlugin,
+    stamina::CharacterStaminaPlugin, stance::CharacterStancePlugin, step_up::CharacterStepUpPlugin,
+    wall_run::CharacterWallRunPlugin,
 };
 
 /*
@@ -23,53 +36,150 @@ use self::{
 
     Things for the next iteration:
 
-    - use newtypes to make data more descriptive, e.g. "turn speed" has a very weird value
-    - set the character's mass manually and make the collider densities 0, for consistency
     - add character interaction, didn't have a clear idea of how that would look for now
     - grounded & jump components:
         - don't make grounded component optional, if the functionality already works why would you not just always use it?
-        - prevent sliding when idle on a slope. either snap transform back to prev position or try putting the rigidbody to sleep
-        - when the grounded check casts a shape, use that shape collision to get the normal directly, the current system causes the character to get stuck a bit on the edge of a slope
-    - crouching component:
-        - use a different system for crouching, either:
-            - resize the collider downwards so character stays grounded
-            - make alternative to resizing like splitting body in multiple parts and rotating those, which would be more accurate also
-        - no magic numbers in crouching code, move these parameters to a config
 */
 
 pub struct CharacterPlugin;
 
 impl Plugin for CharacterPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins((
-            CharacterMovementPlugin,
-            CharacterRotationPlugin,
-            CharacterJumpPlugin,
-            CharacterCrouchPlugin,
-        ));
+        app.add_event::<CharacterEvent>()
+            .configure_sets(
+                Update,
+                (
+                    CharacterSet::Input,
+                    CharacterSet::Rotation,
+                    CharacterSet::Movement,
+                    CharacterSet::Camera,
+                )
+                    .chain(),
+            )
+            .configure_sets(
+                FixedUpdate,
+                (
+                    CharacterSet::Input,
+                    CharacterSet::GroundCheck,
+                    CharacterSet::Rotation,
+                    CharacterSet::Movement,
+                )
+                    .chain()
+                    .before(PhysicsSet::SyncBackend),
+            )
+            .configure_sets(
+                FixedUpdate,
+                (
+                    MovementSet::State,
+                    MovementSet::Jump,
+                    MovementSet::Dash,
+                    MovementSet::Slide,
+                    MovementSet::WallRun,
+                    MovementSet::StepUp,
+                    MovementSet::Move,
+                )
+                    .chain()
+                    .in_set(CharacterSet::Movement),
+            )
+            .add_plugins((
+                CharacterMovementPlugin,
+                CharacterRotationPlugin,
+                CharacterJumpPlugin,
+                CharacterStancePlugin,
+                CharacterWallRunPlugin,
+                CharacterStepUpPlugin,
+                CharacterPlatformCarryPlugin,
+                CharacterSlidePlugin,
+                CharacterStaminaPlugin,
+                CharacterDashPlugin,
+                CharacterKinematicPlugin,
+            ));
     }
 }
 
+/// The system sets of the character controller, use these to order your own systems around it.
+///
+/// The sets are ordered in the listed order, in both `Update` and `FixedUpdate`.
+/// The physics sets run in `FixedUpdate` before rapier's `PhysicsSet::SyncBackend`, this needs rapier to run in `FixedUpdate` with `RapierPhysicsPlugin::in_fixed_schedule`.
+///
+/// * Custom input sources or AI should set the character inputs in `Input`, in `Update` for per frame input or `FixedUpdate` for per physics step input.
+/// * Camera systems should run in `Camera` in `Update`, this is after the head is rotated.
+#[derive(SystemSet, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CharacterSet {
+    /// Sets the inputs of the character, like `PlayerMovementInput` does.
+    Input,
+    /// Checks for ground and walls, in `FixedUpdate`.
+    GroundCheck,
+    /// Turns the body and moves the head up and down in `Update`.
+    /// In `FixedUpdate` this carries the character along with the platform it stands on, and applies the heading to the body before it moves.
+    Rotation,
+    /// Changes the velocity and forces of the character, and everything that builds on it like jumping and stances, in `FixedUpdate`.
+    /// In `Update` this handles the running state and its events.
+    Movement,
+    /// Empty, for camera systems that should run after the character is rotated.
+    Camera,
+}
+
+/// The order of the systems inside `CharacterSet::Movement` in `FixedUpdate`.
+///
+/// Most of these change the velocity of the character, so they run in a fixed order to give the same result every step.
+#[derive(SystemSet, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub(crate) enum MovementSet {
+    /// Stamina and stances, these decide what the character can do this step.
+    State,
+    /// Ground, coyote, air and wall jumps.
+    Jump,
+    Dash,
+    Slide,
+    WallRun,
+    StepUp,
+    /// The normal movement, after everything that can turn it off or change the velocity it pushes towards.
+    Move,
+}
+
 /// The main character component, holds state and current inputs.
 ///
 /// NOTE: To spawn a character it is recommended to use the `CharacterSpawner` helper struct.
-/// 
+///
 /// `is_active` and `draw_movement_gizmos` are the only fields you might want to set manually.
+///
+/// NOTE: The root transform is rotated to match the yaw every frame, so rotating it directly is undone. Turn the character with `set_yaw` instead, e.g. after a teleport.
 #[derive(Component, Debug)]
 pub struct Character {
     pub is_active: bool,
     pub is_running: bool,
     pub movement_input: Vec3,
-    pub rotation_input: Vec3,
+
+    /// Look input that is a distance, like mouse movement in pixels. X turns to the right and Y looks up.
+    ///
+    /// Input sources should add to this instead of overwriting it, the rotation systems reset it after turning the character.
+    pub look_delta: Vec2,
+
+    /// Look input that is a rate, like a stick between -1 and 1. X turns to the right and Y looks up.
+    ///
+    /// This is scaled by the frame time and kept until the input source changes it.
+    pub look_rate: Vec2,
+
     pub movement_direction: Vec3,
     pub corrective_direction: Vec3,
 
     /// Visualize the movement forces acting on this character.
-    /// 
+    ///
     /// * Cyan = the current velocity.
     /// * Purple = the target velocity.
-    /// * Red = a correcting force to reduce drifting and sliding.
+    /// * Red = the movement force pushing the character towards the target velocity.
     pub draw_movement_gizmos: bool,
+
+    /// The value of `is_running` last frame, used to send running events.
+    was_running: bool,
+
+    /// How far the body is turned around the Y axis, in radians. The root transform is rotated to match this.
+    ///
+    /// * NOTE: this is read from the root transform when the character is added, after that the root transform is overwritten with it every frame.
+    yaw: f32,
+
+    /// How far the head is tilted up, in radians. Negative when looking down, the head transform is rotated to match this.
+    pitch: f32,
 }
 
 impl Character {
@@ -81,6 +191,28 @@ impl Character {
     pub fn activate(&mut self) {
         self.is_active = true;
     }
+
+    /// Returns how far the body is turned around the Y axis, in radians.
+    pub fn yaw(&self) -> f32 {
+        self.yaw
+    }
+
+    /// Returns how far the head is tilted up, in radians. Negative when looking down.
+    pub fn pitch(&self) -> f32 {
+        self.pitch
+    }
+
+    /// Turns the body to the given angle around the Y axis, in radians. E.g. to face a direction after teleporting.
+    ///
+    /// The angle is wrapped to the range from -PI to PI.
+    pub fn set_yaw(&mut self, yaw: f32) {
+        self.yaw = wrap_angle(yaw);
+    }
+
+    /// Tilts the head to the given angle, in radians. This is still limited by the look limits in the `CharacterConfig`.
+    pub fn set_pitch(&mut self, pitch: f32) {
+        self.pitch = pitch;
+    }
 }
 
 impl Default for Character {
@@ -89,10 +221,72 @@ impl Default for Character {
             is_active: true,
             is_running: false,
             movement_input: Vec3::ZERO,
-            rotation_input: Vec3::ZERO,
+            look_delta: Vec2::ZERO,
+            look_rate: Vec2::ZERO,
             movement_direction: Vec3::ZERO,
             corrective_direction: Vec3::ZERO,
             draw_movement_gizmos: false,
+            was_running: false,
+            yaw: 0.0,
+            pitch: 0.0,
+        }
+    }
+}
+
+/// Events sent when the state of a character changes, so gameplay code doesn't need to poll the components.
+///
+/// Each event holds the character's root entity.
+#[derive(Event, Clone, Copy, Debug)]
+pub enum CharacterEvent {
+    Jumped {
+        entity: Entity,
+    },
+    /// `impact_speed` is the downward speed at the last ground check before landing, `airtime` is how long the character was in the air in seconds.
+    Landed {
+        entity: Entity,
+        impact_speed: f32,
+        airtime: f32,
+    },
+    LeftGround {
+        entity: Entity,
+    },
+    StartedRunning {
+        entity: Entity,
+    },
+    StoppedRunning {
+        entity: Entity,
+    },
+    Crouched {
+        entity: Entity,
+    },
+    WentProne {
+        entity: Entity,
+    },
+    Stood {
+        entity: Entity,
+    },
+    SlideStarted {
+        entity: Entity,
+    },
+    SlideEnded {
+        entity: Entity,
+    },
+}
+
+impl CharacterEvent {
+    /// Returns the root entity of the character this event is about.
+    pub fn entity(&self) -> Entity {
+        match *self {
+            CharacterEvent::Jumped { entity }
+            | CharacterEvent::Landed { entity, .. }
+            | CharacterEvent::LeftGround { entity }
+            | CharacterEvent::StartedRunning { entity }
+            | CharacterEvent::StoppedRunning { entity }
+            | CharacterEvent::Crouched { entity }
+            | CharacterEvent::WentProne { entity }
+            | CharacterEvent::Stood { entity }
+            | CharacterEvent::SlideStarted { entity }
+            | CharacterEvent::SlideEnded { entity } => entity,
         }
     }
 }
@@ -102,3 +296,19 @@ pub struct CharacterHead;
 
 #[derive(Component)]
 pub struct CharacterBody;
+
+/// The visible mesh of the character body, this is a child of the `CharacterBody` so it can be scaled without scaling the collider.
+#[derive(Component)]
+pub struct CharacterBodyMesh;
+
+// Utilities
+
+/// Returns the angle wrapped to the range from -PI to PI, in radians.
+fn wrap_angle(angle: f32) -> f32 {
+    (angle + PI).rem_euclid(TAU) - PI
+}
+
+/// Returns the vector with its Y component set to 0.
+pub(crate) fn vector_without_y(vector: Vec3) -> Vec3 {
+    Vec3::new(vector.x, 0.0, vector.z)
+}
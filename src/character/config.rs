@@ -1,33 +1,85 @@
 use bevy::prelude::*;
 
+use crate::grounded::Grounded;
+
 #[derive(Component)]
 pub struct CharacterConfig {
     pub walk_speed: f32,
-    pub walk_strength: f32,
     pub run_speed: f32,
-    pub run_strength: f32,
-    pub turn_speed: f32,
 
-    /// The max angle the character can look up or down, in degrees.
-    pub vertical_rotation_limit_degrees: f32,
-    pub jump_strength: f32,
+    /// How fast the look input turns the character and moves the head up and down.
+    pub look_sensitivity: LookSensitivity,
+
+    /// If the character is moved by forces or by changing its velocity directly.
+    pub movement_mode: MovementMode,
+
+    /// How fast the character speeds up, slows down and turns around while grounded.
+    pub ground_movement: MovementProfile,
+
+    /// How fast the character speeds up, slows down and turns around while in the air.
+    pub air_movement: MovementProfile,
+
+    /// The max angle the character can look up, in degrees. Limited to 90.
+    pub max_look_up_degrees: f32,
+
+    /// The max angle the character can look down, in degrees. Limited to 90.
+    pub max_look_down_degrees: f32,
+
+    /// The height of a jump when the jump input is released right away, in meters.
+    pub min_jump_height: f32,
+
+    /// The height of a jump when the jump input is held until the top of the jump, in meters.
+    pub max_jump_height: f32,
+
+    /// How a jump is cut short when the jump input is released before reaching `max_jump_height`.
+    pub jump_cutoff: JumpCutoff,
+
+    /// The speed the character is launched with when jumping off a wall.
+    pub wall_jump_speed: f32,
+
+    /// The angle above the horizontal that the character is launched at when jumping off a wall, in degrees.
+    pub wall_jump_angle_degrees: f32,
+
+    /// The steepest slope the character can walk on, in degrees. On steeper slopes the character slides down.
+    pub max_slope_angle_degrees: f32,
+
+    /// The strength of the force pushing the character down slopes that are too steep to walk on.
+    ///
+    /// * Note: not used with the kinematic backend, the character controller slides down steep slopes by itself.
+    pub slide_strength: f32,
 
-    /// What movement will be multiplied by when in the air
-    pub aerial_multiplier: f32,
+    /// If the character should stay in place on walkable slopes when it has no movement input.
+    ///
+    /// This cancels out the part of gravity that pulls the character down the slope, other forces and impulses still move it.
+    ///
+    /// * Note: not used with the kinematic backend, the character controller doesn't slide down walkable slopes.
+    pub prevent_idle_sliding: bool,
+
+    /// The total height of the character when fully crouched, including the head.
+    pub crouch_height: f32,
+
+    /// The total height of the character when prone, including the head. This is also how thick the lying body is.
+    pub prone_height: f32,
+
+    /// How long the body is when lying prone, from head to feet.
+    pub prone_length: f32,
+
+    /// How fast the height of the character changes when switching stances, in meters per second.
+    pub stance_transition_speed: f32,
+
+    /// What the movement speed will be multiplied by when crouched.
+    pub crouch_speed_multiplier: f32,
+
+    /// What the movement speed will be multiplied by when prone.
+    pub prone_speed_multiplier: f32,
 }
 
 impl CharacterConfig {
-    pub fn get_movement_strength(&self, is_grounded: bool, is_running: bool) -> f32 {
-        let mut strength = match is_running {
-            false => self.walk_strength,
-            true => self.run_strength,
-        };
-
-        if !is_grounded {
-            strength *= self.aerial_multiplier;
+    pub fn get_movement_profile(&self, is_grounded: bool) -> &MovementProfile {
+        match is_grounded {
+            true => &self.ground_movement,
+            false => &self.air_movement,
         }
-
-        strength
     }
 
     pub fn get_movement_speed(&self, is_running: bool) -> f32 {
@@ -36,19 +88,303 @@ impl CharacterConfig {
             true => self.run_speed,
         }
     }
+
+    /// Returns the upwards velocity needed to reach `max_jump_height` with the given gravity strength.
+    pub fn max_jump_velocity(&self, gravity: f32) -> f32 {
+        velocity_for_jump_height(self.max_jump_height, gravity)
+    }
+
+    /// Returns true if the character is standing on a slope that is too steep to walk on.
+    pub fn is_sliding(&self, grounded: &Grounded) -> bool {
+        grounded.is_grounded()
+            && grounded
+                .slope_angle_degrees()
+                .is_some_and(|angle| angle > self.max_slope_angle_degrees)
+    }
+
+    /// Returns the velocity a wall jump launches the character with, away from the wall and upwards.
+    pub fn wall_jump_velocity(&self, wall_normal: Direction3d) -> Vec3 {
+        let away_from_wall = Vec3::new(wall_normal.x, 0.0, wall_normal.z).normalize_or_zero();
+        let angle = self.wall_jump_angle_degrees.to_radians();
+
+        (away_from_wall * angle.cos() + Vec3::Y * angle.sin()) * self.wall_jump_speed
+    }
+
+    /// Returns the lowest and highest pitch the head can have, in radians.
+    ///
+    /// The limits can't go past straight up or down, the view would flip upside down.
+    pub fn pitch_limits(&self) -> (f32, f32) {
+        (
+            -self.max_look_down_degrees.clamp(0.0, 90.0).to_radians(),
+            self.max_look_up_degrees.clamp(0.0, 90.0).to_radians(),
+        )
+    }
+
+    /// Returns the upwards velocity needed to reach `min_jump_height` with the given gravity strength.
+    pub fn min_jump_velocity(&self, gravity: f32) -> f32 {
+        velocity_for_jump_height(self.min_jump_height, gravity)
+    }
 }
 
 impl Default for CharacterConfig {
     fn default() -> Self {
         Self {
             walk_speed: 4.0,
-            walk_strength: 2.5,
             run_speed: 8.0,
-            run_strength: 4.0,
-            jump_strength: 3.0,
-            turn_speed: 0.0007,
-            vertical_rotation_limit_degrees: 90.0,
-            aerial_multiplier: 0.5,
+            movement_mode: MovementMode::Force,
+            ground_movement: MovementProfile {
+                acceleration: AccelerationCurve::new(vec![
+                    Vec2::new(0.0, 12.0),
+                    Vec2::new(4.0, 8.0),
+                    Vec2::new(8.0, 5.0),
+                ]),
+                deceleration: AccelerationCurve::constant(14.0),
+                turn_around: AccelerationCurve::constant(20.0),
+            },
+            air_movement: MovementProfile {
+                acceleration: AccelerationCurve::constant(4.0),
+                deceleration: AccelerationCurve::constant(1.0),
+                turn_around: AccelerationCurve::constant(6.0),
+            },
+            min_jump_height: 0.5,
+            max_jump_height: 1.5,
+            jump_cutoff: JumpCutoff::VelocityClamp,
+            wall_jump_speed: 7.0,
+            wall_jump_angle_degrees: 50.0,
+            look_sensitivity: LookSensitivity::default(),
+            max_look_up_degrees: 90.0,
+            max_look_down_degrees: 90.0,
+            max_slope_angle_degrees: 45.0,
+            slide_strength: 2.0,
+            prevent_idle_sliding: true,
+            crouch_height: 1.2,
+            prone_height: 0.7,
+            prone_length: 1.8,
+            stance_transition_speed: 5.0,
+            crouch_speed_multiplier: 0.5,
+            prone_speed_multiplier: 0.25,
+        }
+    }
+}
+
+/// How fast the look input of the `Character` rotates it, with separate values for each axis.
+#[derive(Clone, Copy, Debug)]
+pub struct LookSensitivity {
+    /// Used for `look_delta` to turn left and right, e.g. for horizontal mouse movement.
+    pub horizontal_delta: DegreesPerPixel,
+    /// Used for `look_delta` to look up and down, e.g. for vertical mouse movement.
+    pub vertical_delta: DegreesPerPixel,
+    /// Used for `look_rate` to turn left and right, this is the speed at full input, e.g. when a stick is pushed all the way.
+    pub horizontal_rate: DegreesPerSecond,
+    /// Used for `look_rate` to look up and down, this is the speed at full input.
+    pub vertical_rate: DegreesPerSecond,
+    /// Swaps looking up and down, for both kinds of input.
+    pub invert_y: bool,
+}
+
+impl LookSensitivity {
+    /// Returns how far the given look inputs rotate the character, in radians. X turns to the right and Y looks up.
+    ///
+    /// `look_delta` is used as is, `look_rate` is scaled by `delta_seconds`.
+    pub fn rotation(&self, look_delta: Vec2, look_rate: Vec2, delta_seconds: f32) -> Vec2 {
+        let rotation = Vec2::new(
+            self.horizontal_delta.radians(look_delta.x)
+                + self.horizontal_rate.radians(look_rate.x * delta_seconds),
+            self.vertical_delta.radians(look_delta.y)
+                + self.vertical_rate.radians(look_rate.y * delta_seconds),
+        );
+
+        match self.invert_y {
+            true => Vec2::new(rotation.x, -rotation.y),
+            false => rotation,
+        }
+    }
+}
+
+impl Default for LookSensitivity {
+    fn default() -> Self {
+        Self {
+            horizontal_delta: DegreesPerPixel(0.04),
+            vertical_delta: DegreesPerPixel(0.04),
+            horizontal_rate: DegreesPerSecond(180.0),
+            vertical_rate: DegreesPerSecond(120.0),
+            invert_y: false,
+        }
+    }
+}
+
+/// A rotation for each pixel of input, like mouse movement.
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+pub struct DegreesPerPixel(pub f32);
+
+impl DegreesPerPixel {
+    /// Returns the rotation for the given amount of pixels, in radians.
+    pub fn radians(self, pixels: f32) -> f32 {
+        (self.0 * pixels).to_radians()
+    }
+}
+
+/// A rotation speed, like how fast a stick turns the character.
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+pub struct DegreesPerSecond(pub f32);
+
+impl DegreesPerSecond {
+    /// Returns the rotation over the given amount of seconds, in radians.
+    pub fn radians(self, seconds: f32) -> f32 {
+        (self.0 * seconds).to_radians()
+    }
+}
+
+/// The ways the character can be moved towards its target velocity.
+///
+/// Both modes use the accelerations from the `MovementProfile`s, only the horizontal velocity is changed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MovementMode {
+    /// Applies a force to the rigidbody, collisions, damping and the physics step all affect the movement. This has a more physical feel.
+    #[default]
+    Force,
+    /// Changes the velocity directly each frame. This is more snappy and predictable, knockback is still slowed down at the movement accelerations instead of being cancelled right away.
+    Velocity,
+}
+
+/// How fast the character changes its velocity towards the target velocity, in m/s².
+///
+/// The rates are looked up on curves by the character's current speed, e.g. to speed up quickly from standing still but slowly near top speed.
+#[derive(Clone, Debug)]
+pub struct MovementProfile {
+    /// Used when speeding up or changing direction by less than 90 degrees.
+    pub acceleration: AccelerationCurve,
+    /// Used when there is no movement input.
+    pub deceleration: AccelerationCurve,
+    /// Used when the movement input points away from the current velocity.
+    pub turn_around: AccelerationCurve,
+}
+
+impl MovementProfile {
+    /// Returns the acceleration to use for moving from the current to the target velocity, both relative to the ground.
+    pub fn get_acceleration(&self, current_velocity: Vec3, target_velocity: Vec3) -> f32 {
+        let speed = current_velocity.length();
+
+        if target_velocity.length() < 0.01 {
+            self.deceleration.sample(speed)
+        } else if current_velocity.dot(target_velocity) < 0.0 {
+            self.turn_around.sample(speed)
+        } else {
+            self.acceleration.sample(speed)
+        }
+    }
+}
+
+/// A curve of accelerations in m/s² by speed in m/s, made of points that are linearly interpolated.
+///
+/// `x` of each point is the speed and `y` the acceleration at that speed, speeds outside the curve use the closest point.
+#[derive(Clone, Debug)]
+pub struct AccelerationCurve {
+    points: Vec<Vec2>,
+}
+
+impl AccelerationCurve {
+    /// Creates a curve through the given points, they don't need to be sorted.
+    pub fn new(mut points: Vec<Vec2>) -> Self {
+        points.sort_by(|a, b| a.x.total_cmp(&b.x));
+
+        Self { points }
+    }
+
+    /// Creates a curve that has the same acceleration at every speed.
+    pub fn constant(acceleration: f32) -> Self {
+        Self::new(vec![Vec2::new(0.0, acceleration)])
+    }
+
+    /// Returns the acceleration at the given speed, 0 if the curve has no points.
+    pub fn sample(&self, speed: f32) -> f32 {
+        let (Some(first), Some(last)) = (self.points.first(), self.points.last()) else {
+            return 0.0;
+        };
+
+        if speed <= first.x {
+            return first.y;
+        }
+
+        if speed >= last.x {
+            return last.y;
         }
+
+        self.points
+            .windows(2)
+            .find(|pair| speed <= pair[1].x)
+            .map_or(last.y, |pair| {
+                let fraction = (speed - pair[0].x) / (pair[1].x - pair[0].x);
+                pair[0].y + (pair[1].y - pair[0].y) * fraction
+            })
+    }
+}
+
+/// The ways a jump can be cut short when the jump input is released early.
+#[derive(Clone, Copy, Debug)]
+pub enum JumpCutoff {
+    /// Limits the upwards velocity to the velocity of a `min_jump_height` jump.
+    VelocityClamp,
+    /// Multiplies gravity by this value until the character starts falling.
+    ExtraGravity { gravity_multiplier: f32 },
+}
+
+/// Returns the upwards velocity needed to reach the given height, ignoring drag.
+fn velocity_for_jump_height(height: f32, gravity: f32) -> f32 {
+    (2.0 * gravity * height.max(0.0)).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_curve_samples_zero() {
+        let curve = AccelerationCurve::new(Vec::new());
+
+        assert_eq!(curve.sample(5.0), 0.0);
+    }
+
+    #[test]
+    fn single_point_curve_is_constant() {
+        let curve = AccelerationCurve::constant(20.0);
+
+        assert_eq!(curve.sample(-1.0), 20.0);
+        assert_eq!(curve.sample(0.0), 20.0);
+        assert_eq!(curve.sample(100.0), 20.0);
+    }
+
+    #[test]
+    fn speeds_outside_the_curve_use_the_closest_point() {
+        let curve = AccelerationCurve::new(vec![Vec2::new(2.0, 30.0), Vec2::new(6.0, 10.0)]);
+
+        assert_eq!(curve.sample(0.0), 30.0);
+        assert_eq!(curve.sample(10.0), 10.0);
+    }
+
+    #[test]
+    fn speeds_between_points_are_interpolated() {
+        let curve = AccelerationCurve::new(vec![
+            Vec2::new(0.0, 40.0),
+            Vec2::new(4.0, 20.0),
+            Vec2::new(8.0, 0.0),
+        ]);
+
+        assert_eq!(curve.sample(2.0), 30.0);
+        assert_eq!(curve.sample(4.0), 20.0);
+        assert_eq!(curve.sample(6.0), 10.0);
+    }
+
+    #[test]
+    fn unsorted_points_are_sorted_by_speed() {
+        let curve = AccelerationCurve::new(vec![
+            Vec2::new(8.0, 0.0),
+            Vec2::new(0.0, 40.0),
+            Vec2::new(4.0, 20.0),
+        ]);
+
+        assert_eq!(curve.sample(-1.0), 40.0);
+        assert_eq!(curve.sample(2.0), 30.0);
+        assert_eq!(curve.sample(9.0), 0.0);
     }
 }
@@ -0,0 +1,181 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::grounded::Grounded;
+
+use super::{vector_without_y, Character, MovementSet};
+
+pub struct CharacterDashPlugin;
+
+impl Plugin for CharacterDashPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            (start_dash, update_dash).chain().in_set(MovementSet::Dash),
+        );
+    }
+}
+
+/// Lets the character dash, a short burst of speed in the movement direction or the facing direction if there is no movement input.
+///
+/// * Set `has_dash_input` to true when the dash button is pressed, it's reset when the input is handled.
+/// * While dashing the normal movement forces are turned off.
+/// * NOTE: without a `Grounded` component the character counts as grounded, so air dashes aren't limited.
+#[derive(Component)]
+pub struct CharacterDash {
+    pub has_dash_input: bool,
+
+    /// How far a dash moves the character, in meters.
+    pub distance: f32,
+
+    /// How long a dash lasts, in seconds.
+    pub duration: f32,
+
+    /// How long after a dash ends before the character can dash again, in seconds.
+    pub cooldown: f32,
+
+    /// How many times the character can dash while in the air, resets when landing.
+    pub max_air_dashes: u32,
+
+    /// If the character should ignore gravity while dashing, this keeps air dashes level.
+    pub suspend_gravity: bool,
+
+    state: Option<DashState>,
+    cooldown_remaining: f32,
+    air_dashes_used: u32,
+}
+
+#[derive(Clone, Copy)]
+struct DashState {
+    direction: Vec3,
+    elapsed: f32,
+    /// The horizontal speed before the dash, the character is slowed back down to this when the dash ends.
+    start_speed: f32,
+}
+
+impl CharacterDash {
+    pub fn new(distance: f32, duration: f32, cooldown: f32, max_air_dashes: u32) -> Self {
+        Self {
+            has_dash_input: false,
+            distance,
+            duration,
+            cooldown,
+            max_air_dashes,
+            suspend_gravity: true,
+            state: None,
+            cooldown_remaining: 0.0,
+            air_dashes_used: 0,
+        }
+    }
+
+    pub fn is_dashing(&self) -> bool {
+        self.state.is_some()
+    }
+
+    /// Returns how long until the character can dash again, in seconds.
+    pub fn cooldown_remaining(&self) -> f32 {
+        self.cooldown_remaining
+    }
+
+    /// Returns how many air dashes the character has left before it needs to land again.
+    pub fn remaining_air_dashes(&self) -> u32 {
+        self.max_air_dashes.saturating_sub(self.air_dashes_used)
+    }
+
+    /// The speed needed to cover `distance` in `duration`.
+    fn speed(&self) -> f32 {
+        match self.duration > 0.0 {
+            true => self.distance / self.duration,
+            false => 0.0,
+        }
+    }
+
+    fn can_dash(&self, is_grounded: bool) -> bool {
+        !self.is_dashing()
+            && self.cooldown_remaining <= 0.0
+            && (is_grounded || self.remaining_air_dashes() > 0)
+    }
+}
+
+fn start_dash(
+    mut characters: Query<(
+        &mut CharacterDash,
+        &Character,
+        &Velocity,
+        &GlobalTransform,
+        Option<&Grounded>,
+    )>,
+    time: Res<Time>,
+) {
+    for (mut dash, character, velocity, global_transform, grounded) in characters.iter_mut() {
+        let is_grounded = match grounded {
+            Some(grounded) => grounded.is_grounded(),
+            None => true,
+        };
+
+        if is_grounded {
+            dash.air_dashes_used = 0;
+        }
+
+        dash.cooldown_remaining = (dash.cooldown_remaining - time.delta_seconds()).max(0.0);
+
+        if !dash.has_dash_input {
+            continue;
+        }
+
+        dash.has_dash_input = false;
+
+        if !character.is_active || !dash.can_dash(is_grounded) {
+            continue;
+        }
+
+        let direction = vector_without_y(character.movement_direction)
+            .try_normalize()
+            .or_else(|| vector_without_y(global_transform.forward()).try_normalize());
+
+        if let Some(direction) = direction {
+            dash.state = Some(DashState {
+                direction,
+                elapsed: 0.0,
+                start_speed: vector_without_y(velocity.linvel).length(),
+            });
+
+            if !is_grounded {
+                dash.air_dashes_used += 1;
+            }
+        }
+    }
+}
+
+fn update_dash(
+    mut characters: Query<(&mut CharacterDash, &mut Velocity, &Character)>,
+    time: Res<Time>,
+) {
+    for (mut dash, mut velocity, character) in characters.iter_mut() {
+        let Some(mut state) = dash.state else {
+            continue;
+        };
+
+        state.elapsed += time.delta_seconds();
+
+        if !character.is_active || state.elapsed >= dash.duration {
+            dash.state = None;
+            dash.cooldown_remaining = dash.cooldown;
+
+            // Without this the character would keep most of the dash speed and move much further than `distance`.
+            let horizontal_velocity =
+                vector_without_y(velocity.linvel).clamp_length_max(state.start_speed);
+            velocity.linvel = horizontal_velocity + Vec3::Y * velocity.linvel.y;
+            continue;
+        }
+
+        let dash_velocity = state.direction * dash.speed();
+
+        velocity.linvel = match dash.suspend_gravity {
+            true => dash_velocity,
+            false => dash_velocity + Vec3::Y * velocity.linvel.y,
+        };
+
+        dash.state = Some(state);
+    }
+}
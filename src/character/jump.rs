@@ -1,37 +1,237 @@
 use bevy::prelude::*;
 use bevy_rapier3d::prelude::*;
 
-use super::config::CharacterConfig;
+use crate::{grounded::Grounded, wall_contact::WallContact};
+
+use super::{
+    config::{CharacterConfig, JumpCutoff},
+    stamina::CharacterStamina,
+    CharacterEvent, MovementSet,
+};
 
 pub struct CharacterJumpPlugin;
 
 impl Plugin for CharacterJumpPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, jump_character);
+        app.add_systems(
+            FixedUpdate,
+            (jump_character, cut_jump_short)
+                .chain()
+                .in_set(MovementSet::Jump),
+        );
     }
 }
 
+/// Handles jumping for the character, this includes coyote time, jump buffering, wall jumps and air jumps.
+///
+/// * Set `has_jump_input` to true when the jump button is pressed, this component decides when the jump actually happens.
+/// * Keep `is_jump_held` true while the jump button is held, releasing it early makes the jump lower.
+/// * NOTE: this entity should also have a `Grounded` component, and a `WallContact` component to be able to wall jump.
+/// * If the entity has a `CharacterStamina` component, it can't jump while exhausted.
 #[derive(Component)]
 pub struct CharacterJump {
     pub has_jump_input: bool,
+    pub is_jump_held: bool,
+
+    /// How long after leaving the ground the character can still jump, in seconds.
+    pub coyote_time: f32,
+
+    /// How long a jump input is remembered when the character can't jump yet, in seconds.
+    pub jump_buffer_time: f32,
+
+    /// How many times the character can jump again while in the air, resets when landing.
+    pub max_air_jumps: u32,
+
+    air_jumps_used: u32,
+    time_since_grounded: f32,
+    time_since_jump_input: Option<f32>,
+
+    /// If the character is moving up from a jump, this is when the jump can still be cut short.
+    is_rising: bool,
 }
 
 impl CharacterJump {
-    pub fn new() -> Self {
+    pub fn new(coyote_time: f32, jump_buffer_time: f32, max_air_jumps: u32) -> Self {
         Self {
             has_jump_input: false,
+            is_jump_held: false,
+            coyote_time,
+            jump_buffer_time,
+            max_air_jumps,
+            air_jumps_used: 0,
+            time_since_grounded: 0.0,
+            time_since_jump_input: None,
+            is_rising: false,
+        }
+    }
+
+    /// Returns true if the character is grounded or only just left the ground.
+    ///
+    /// * With a `coyote_time` of 0 this is only true while grounded.
+    pub fn is_within_coyote_time(&self) -> bool {
+        self.time_since_grounded <= self.coyote_time
+    }
+
+    /// Returns how many air jumps the character has left before it needs to land again.
+    pub fn remaining_air_jumps(&self) -> u32 {
+        self.max_air_jumps.saturating_sub(self.air_jumps_used)
+    }
+
+    /// Returns true if there is a jump input that hasn't been used yet.
+    pub fn has_buffered_jump(&self) -> bool {
+        self.time_since_jump_input.is_some()
+    }
+
+    fn update_timers(&mut self, is_grounded: bool, delta_seconds: f32) {
+        self.time_since_grounded = match is_grounded {
+            true => 0.0,
+            false => self.time_since_grounded + delta_seconds,
+        };
+
+        if is_grounded {
+            self.air_jumps_used = 0;
         }
+
+        self.time_since_jump_input = self
+            .time_since_jump_input
+            .map(|time| time + delta_seconds)
+            .filter(|time| *time <= self.jump_buffer_time);
+
+        if self.has_jump_input {
+            self.has_jump_input = false;
+            self.time_since_jump_input = Some(0.0);
+        }
+    }
+
+    /// Clears the buffered input and ends the coyote time, so the same input can't cause a second jump.
+    fn consume_jump(&mut self) {
+        self.time_since_jump_input = None;
+        self.time_since_grounded = f32::INFINITY;
+        self.is_rising = true;
+    }
+
+    fn consume_air_jump(&mut self) {
+        self.consume_jump();
+        self.air_jumps_used += 1;
     }
 }
 
+#[allow(clippy::type_complexity)]
 fn jump_character(
-    mut characters: Query<(&mut ExternalImpulse, &mut CharacterJump, &CharacterConfig)>,
+    mut characters: Query<(
+        &mut Velocity,
+        &mut CharacterJump,
+        &CharacterConfig,
+        &Grounded,
+        Option<&WallContact>,
+        Option<&CharacterStamina>,
+        Entity,
+    )>,
+    mut character_events: EventWriter<CharacterEvent>,
+    rapier_config: Res<RapierConfiguration>,
+    time: Res<Time>,
 ) {
-    for (mut impulse, mut jump, config) in characters
-        .iter_mut()
-        .filter(|(_, jump, _)| jump.has_jump_input)
+    let gravity = rapier_config.gravity.length();
+
+    for (mut velocity, mut jump, config, grounded, wall_contact, stamina, entity) in
+        characters.iter_mut()
     {
-        jump.has_jump_input = false;
-        impulse.impulse = Vec3::Y * config.jump_strength;
+        // Slopes that are too steep don't count as ground, the jump input stays buffered until the character is off it.
+        let is_sliding = config.is_sliding(grounded);
+        jump.update_timers(grounded.is_grounded() && !is_sliding, time.delta_seconds());
+
+        let is_exhausted = stamina.is_some_and(|stamina| stamina.is_exhausted());
+
+        if !jump.has_buffered_jump() || is_sliding || is_exhausted {
+            continue;
+        }
+
+        if jump.is_within_coyote_time() {
+            jump.consume_jump();
+        } else if let Some(wall_normal) = wall_contact.and_then(|wall| wall.wall_normal()) {
+            jump.consume_jump();
+            velocity.linvel = config.wall_jump_velocity(wall_normal);
+            character_events.send(CharacterEvent::Jumped { entity });
+            continue;
+        } else if jump.remaining_air_jumps() > 0 {
+            jump.consume_air_jump();
+        } else {
+            continue;
+        }
+
+        // Setting the velocity instead of adding to it also cancels any falling speed, so air jumps are always the same height.
+        velocity.linvel.y = config.max_jump_velocity(gravity);
+        character_events.send(CharacterEvent::Jumped { entity });
+    }
+}
+
+fn cut_jump_short(
+    mut characters: Query<(&mut Velocity, &mut CharacterJump, &CharacterConfig)>,
+    rapier_config: Res<RapierConfiguration>,
+    time: Res<Time>,
+) {
+    let gravity = rapier_config.gravity.length();
+
+    for (mut velocity, mut jump, config) in
+        characters.iter_mut().filter(|(_, jump, _)| jump.is_rising)
+    {
+        if velocity.linvel.y <= 0.0 {
+            jump.is_rising = false;
+            continue;
+        }
+
+        if jump.is_jump_held {
+            continue;
+        }
+
+        match config.jump_cutoff {
+            JumpCutoff::VelocityClamp => {
+                velocity.linvel.y = velocity.linvel.y.min(config.min_jump_velocity(gravity));
+                jump.is_rising = false;
+            }
+            JumpCutoff::ExtraGravity { gravity_multiplier } => {
+                velocity.linvel.y -= gravity * (gravity_multiplier - 1.0) * time.delta_seconds();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grounded_character_can_jump_without_coyote_time() {
+        let mut jump = CharacterJump::new(0.0, 0.1, 0);
+
+        jump.update_timers(true, 0.02);
+        assert!(jump.is_within_coyote_time());
+
+        jump.update_timers(false, 0.02);
+        assert!(!jump.is_within_coyote_time());
+    }
+
+    #[test]
+    fn coyote_time_ends_after_leaving_the_ground() {
+        let mut jump = CharacterJump::new(0.1, 0.1, 0);
+
+        jump.update_timers(true, 0.02);
+        jump.update_timers(false, 0.05);
+        assert!(jump.is_within_coyote_time());
+
+        jump.update_timers(false, 0.1);
+        assert!(!jump.is_within_coyote_time());
+    }
+
+    #[test]
+    fn consumed_jump_ends_coyote_time() {
+        let mut jump = CharacterJump::new(0.0, 0.1, 0);
+
+        jump.update_timers(true, 0.02);
+        jump.consume_jump();
+        assert!(!jump.is_within_coyote_time());
+
+        jump.update_timers(false, 0.02);
+        assert!(!jump.is_within_coyote_time());
     }
 }
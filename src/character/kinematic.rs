@@ -0,0 +1,113 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use super::{config::CharacterConfig, Character, CharacterSet, MovementSet};
+
+pub struct CharacterKinematicPlugin;
+
+impl Plugin for CharacterKinematicPlugin {
+    fn build(&self, app: &mut App) {
+        // The controller is moved after all the movement systems, so their velocity changes reach it in the same step.
+        app.add_systems(
+            FixedUpdate,
+            (
+                update_controller_settings.in_set(MovementSet::Move),
+                move_kinematic_character
+                    .after(CharacterSet::Movement)
+                    .before(PhysicsSet::SyncBackend),
+            ),
+        );
+    }
+}
+
+/// The physics setup that moves the character.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CharacterBackend {
+    /// A dynamic rigidbody that is pushed around by forces, and pushed by other bodies.
+    #[default]
+    Dynamic,
+    /// A kinematic rigidbody moved by rapier's `KinematicCharacterController`, this handles steps, slopes and snapping to the ground.
+    ///
+    /// The character's `Velocity` is integrated by this plugin, `ExternalForce` and `ExternalImpulse` have no effect and movement always uses `MovementMode::Velocity`.
+    Kinematic,
+}
+
+/// Returns a `KinematicCharacterController` with a capsule shape that covers the whole character, including the head.
+pub fn build_kinematic_controller(
+    height: f32,
+    radius: f32,
+    max_step_height: f32,
+    config: &CharacterConfig,
+) -> KinematicCharacterController {
+    KinematicCharacterController {
+        custom_shape: Some(build_controller_shape(height, radius)),
+        up: Vec3::Y,
+        offset: CharacterLength::Absolute(0.01),
+        slide: true,
+        autostep: Some(CharacterAutostep {
+            max_height: CharacterLength::Absolute(max_step_height),
+            min_width: CharacterLength::Absolute(0.1),
+            include_dynamic_bodies: false,
+        }),
+        max_slope_climb_angle: config.max_slope_angle_degrees.to_radians(),
+        min_slope_slide_angle: config.max_slope_angle_degrees.to_radians(),
+        snap_to_ground: Some(CharacterLength::Absolute(0.2)),
+        ..default()
+    }
+}
+
+/// Returns a vertical capsule that reaches from the feet up to the given height, with its offset from the character root.
+pub fn build_controller_shape(height: f32, radius: f32) -> (Collider, Vec3, Quat) {
+    let half_straight_height = (height / 2.0 - radius).max(0.0);
+
+    (
+        Collider::capsule_y(half_straight_height, radius),
+        Vec3::Y * height / 2.0,
+        Quat::IDENTITY,
+    )
+}
+
+/// Keeps the slope limits of the controller the same as the `CharacterConfig`.
+fn update_controller_settings(
+    mut characters: Query<(&mut KinematicCharacterController, &CharacterConfig)>,
+) {
+    for (mut controller, config) in characters.iter_mut() {
+        let max_slope_angle = config.max_slope_angle_degrees.to_radians();
+
+        if controller.max_slope_climb_angle != max_slope_angle {
+            controller.max_slope_climb_angle = max_slope_angle;
+            controller.min_slope_slide_angle = max_slope_angle;
+        }
+    }
+}
+
+/// Applies gravity to the character's velocity, and passes the velocity to the controller as this frame's translation.
+fn move_kinematic_character(
+    mut characters: Query<(
+        &mut KinematicCharacterController,
+        &mut Velocity,
+        &Character,
+        Option<&KinematicCharacterControllerOutput>,
+    )>,
+    rapier_config: Res<RapierConfiguration>,
+    time: Res<Time>,
+) {
+    for (mut controller, mut velocity, character, output) in characters.iter_mut() {
+        if let Some(output) = output {
+            // The controller doesn't change the velocity, so remove what was blocked by the ground or a ceiling last frame.
+            let hit_ceiling = velocity.linvel.y > 0.0
+                && output.effective_translation.y < output.desired_translation.y * 0.5;
+
+            if (output.grounded && velocity.linvel.y < 0.0) || hit_ceiling {
+                velocity.linvel.y = 0.0;
+            }
+        }
+
+        if !character.is_active {
+            continue;
+        }
+
+        velocity.linvel += rapier_config.gravity * time.delta_seconds();
+        controller.translation = Some(velocity.linvel * time.delta_seconds());
+    }
+}
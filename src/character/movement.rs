@@ -3,98 +3,211 @@ use bevy_rapier3d::prelude::*;
 
 use crate::grounded::Grounded;
 
-use super::{config::CharacterConfig, Character};
+use super::{
+    config::{CharacterConfig, MovementMode},
+    dash::CharacterDash,
+    platform_carry::CharacterPlatformCarry,
+    slide::CharacterSlide,
+    stance::CharacterStance,
+    vector_without_y,
+    wall_run::CharacterWallRun,
+    Character, CharacterEvent, CharacterSet, MovementSet,
+};
 
 pub struct CharacterMovementPlugin;
 
 impl Plugin for CharacterMovementPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(
+            FixedUpdate,
+            (
+                update_movement_direction,
+                update_corrective_direction,
+                move_character,
+            )
+                .chain()
+                .in_set(MovementSet::Move),
+        )
+        .add_systems(
             Update,
             (
-                (
-                    update_movement_direction,
-                    update_corrective_direction,
-                    move_character,
-                )
-                    .chain(),
-                stop_running_if_no_movement_input,
+                (stop_running_if_no_movement_input, send_running_events)
+                    .chain()
+                    .in_set(CharacterSet::Movement),
                 draw_gizmos,
             ),
         );
     }
 }
 
+#[allow(clippy::type_complexity)]
 fn update_movement_direction(
-    mut characters: Query<(&mut Character, &Transform, Option<&Grounded>)>,
+    mut characters: Query<(
+        &mut Character,
+        &CharacterConfig,
+        &Transform,
+        Option<&Grounded>,
+        Option<&CharacterWallRun>,
+    )>,
 ) {
-    for (mut character, transform, grounded) in characters
+    for (mut character, config, transform, grounded, wall_run) in characters
         .iter_mut()
-        .filter(|(character, _, _)| character.is_active)
+        .filter(|(character, _, _, _, _)| character.is_active)
     {
         let ground_rotation = get_ground_rotation(grounded).unwrap_or(Quat::IDENTITY);
 
-        let movement_direction = align_direction_to_ground(
+        let mut movement_direction = align_direction_to_ground(
             ground_rotation,
             transform.rotation,
             character.movement_input,
         );
 
+        if let Some(wall_normal) = wall_run.and_then(|wall_run| wall_run.wall_normal()) {
+            movement_direction = align_direction_to_wall(wall_normal, movement_direction);
+        }
+
+        if let Some(downhill) = get_sliding_downhill_direction(config, grounded) {
+            movement_direction = remove_uphill_component(downhill, movement_direction);
+        }
+
         character.movement_direction = movement_direction;
     }
 }
 
+#[allow(clippy::type_complexity)]
 fn update_corrective_direction(
     mut characters: Query<(
         &mut Character,
         &CharacterConfig,
         &Velocity,
         Option<&Grounded>,
+        Option<&CharacterPlatformCarry>,
+        Option<&CharacterStance>,
     )>,
 ) {
-    for (mut character, config, velocity, grounded) in characters
+    for (mut character, config, velocity, grounded, platform_carry, stance) in characters
         .iter_mut()
-        .filter(|(character, _, _, _)| character.is_active)
+        .filter(|(character, _, _, _, _, _)| character.is_active)
     {
         let treshold = 0.00001;
-        let delta = (character.movement_direction
-            * config.get_movement_speed(character.is_running))
-            - velocity.linvel;
-
-        character.corrective_direction = if delta.length() > treshold {
-            match grounded {
-                Some(grounded) => match grounded.ground_rotation() {
-                    Some(rotation) => rotation * vector_without_y(delta).normalize_or_zero(),
-                    None => Vec3::ZERO,
-                },
-                None => vector_without_y(delta).normalize_or_zero(),
-            }
+        let delta =
+            get_target_velocity(&character, config, stance, platform_carry) - velocity.linvel;
+
+        // In the air there is no ground to align to, the direction stays horizontal.
+        let corrective_direction = if delta.length() > treshold {
+            get_ground_rotation(grounded).unwrap_or(Quat::IDENTITY)
+                * vector_without_y(delta).normalize_or_zero()
         } else {
             Vec3::ZERO
-        }
+        };
+
+        character.corrective_direction = match get_sliding_downhill_direction(config, grounded) {
+            Some(downhill) => remove_uphill_component(downhill, corrective_direction),
+            None => corrective_direction,
+        };
     }
 }
 
+/// Moves the character towards its target velocity, with the acceleration from the grounded or air movement profile.
+#[allow(clippy::type_complexity)]
 fn move_character(
     mut characters: Query<(
         &mut ExternalForce,
         &Character,
         &CharacterConfig,
+        &mut Velocity,
+        &ReadMassProperties,
         Option<&Grounded>,
+        Option<&CharacterPlatformCarry>,
+        Option<&CharacterStance>,
+        Option<&CharacterSlide>,
+        Option<&CharacterDash>,
+        Has<KinematicCharacterController>,
     )>,
+    rapier_config: Res<RapierConfiguration>,
+    time: Res<Time>,
 ) {
-    for (mut force, character, config, grounded) in characters
+    for (
+        mut force,
+        character,
+        config,
+        mut velocity,
+        mass_properties,
+        grounded,
+        platform_carry,
+        stance,
+        slide,
+        dash,
+        is_kinematic,
+    ) in characters
         .iter_mut()
-        .filter(|(_, character, _, _)| character.is_active)
+        .filter(|(_, character, ..)| character.is_active)
     {
-        let combined_direction = character.movement_direction + character.corrective_direction;
         let is_grounded = match grounded {
             Some(grounded) => grounded.is_grounded(),
             None => true,
         };
-        let strength = config.get_movement_strength(is_grounded, character.is_running);
+        let mass = mass_properties.get().mass;
+
+        // Forces don't move kinematic bodies, the character controller handles sliding down steep slopes and standing still on walkable ones.
+        let slide_force = match is_kinematic {
+            true => Vec3::ZERO,
+            false => get_sliding_downhill_direction(config, grounded)
+                .map_or(Vec3::ZERO, |downhill| downhill * config.slide_strength),
+        };
+
+        // Slides and dashes set the velocity directly, the normal movement forces would slow them down.
+        if slide.is_some_and(|slide| slide.is_sliding())
+            || dash.is_some_and(|dash| dash.is_dashing())
+        {
+            force.force = slide_force;
+            continue;
+        }
+
+        let anti_slide_force =
+            match !is_kinematic && should_hold_in_place(character, config, grounded) {
+                true => get_anti_slide_force(grounded, rapier_config.gravity, mass),
+                false => Vec3::ZERO,
+            };
+
+        // Velocities relative to the platform the character stands on, so walking on a moving platform feels the same.
+        let platform_velocity =
+            platform_carry.map_or(Vec3::ZERO, |carry| carry.platform_velocity());
+        let target_velocity =
+            vector_without_y(get_target_velocity(character, config, stance, None));
+        let current_velocity = vector_without_y(velocity.linvel - platform_velocity);
+
+        let velocity_difference = target_velocity - current_velocity;
+        let acceleration = config
+            .get_movement_profile(is_grounded)
+            .get_acceleration(current_velocity, target_velocity);
+
+        // Kinematic bodies always change the velocity directly.
+        let movement_mode = match is_kinematic {
+            true => MovementMode::Velocity,
+            false => config.movement_mode,
+        };
+
+        match movement_mode {
+            MovementMode::Force => {
+                let acceleration = limit_acceleration(
+                    acceleration,
+                    velocity_difference.length(),
+                    time.delta_seconds(),
+                );
 
-        force.force = combined_direction * strength;
+                force.force = character.corrective_direction * acceleration * mass
+                    + slide_force
+                    + anti_slide_force;
+            }
+            MovementMode::Velocity => {
+                // Vertical velocity is left alone, so jumping, falling and slopes still work the same.
+                velocity.linvel +=
+                    velocity_difference.clamp_length_max(acceleration * time.delta_seconds());
+
+                force.force = slide_force + anti_slide_force;
+            }
+        }
     }
 }
 
@@ -107,55 +220,94 @@ fn stop_running_if_no_movement_input(mut characters: Query<&mut Character>) {
     }
 }
 
+fn send_running_events(
+    mut characters: Query<(&mut Character, Entity)>,
+    mut character_events: EventWriter<CharacterEvent>,
+) {
+    for (mut character, entity) in characters
+        .iter_mut()
+        .filter(|(character, _)| character.is_running != character.was_running)
+    {
+        character.was_running = character.is_running;
+
+        character_events.send(match character.is_running {
+            true => CharacterEvent::StartedRunning { entity },
+            false => CharacterEvent::StoppedRunning { entity },
+        });
+    }
+}
+
 // Gizmos
 
+#[allow(clippy::type_complexity)]
 fn draw_gizmos(
     characters: Query<(
         &Character,
         &GlobalTransform,
         &Velocity,
+        &ExternalForce,
         &CharacterConfig,
-        Option<&Grounded>,
+        Option<&CharacterStance>,
     )>,
     mut gizmos: Gizmos,
 ) {
     let position_offset = Vec3::Y * 0.05;
     let current_velocity_color = Color::CYAN;
     let target_velocity_color = Color::FUCHSIA;
-    let corrective_force_color = Color::RED;
+    let movement_force_color = Color::RED;
     let length = 0.4;
 
-    for (character, global_transform, velocity, config, grounded) in characters
+    for (character, global_transform, velocity, force, config, stance) in characters
         .iter()
-        .filter(|(character, _, _, _, _)| character.draw_movement_gizmos)
+        .filter(|(character, _, _, _, _, _)| character.draw_movement_gizmos)
     {
         let position = global_transform.translation() + position_offset;
-        let is_running = character.is_running;
-        let is_grounded = match grounded {
-            Some(grounded) => grounded.is_grounded(),
-            None => true,
-        };
 
         gizmos.ray(position, velocity.linvel * length, current_velocity_color);
 
         gizmos.ray(
             position,
-            character.movement_direction * length * config.get_movement_speed(is_running),
+            character.movement_direction * length * get_movement_speed(character, config, stance),
             target_velocity_color,
         );
 
-        gizmos.ray(
-            position,
-            character.corrective_direction
-                * length
-                * config.get_movement_strength(is_grounded, is_running),
-            corrective_force_color,
-        );
+        gizmos.ray(position, force.force * length, movement_force_color);
     }
 }
 
 // Utilities
 
+/// Returns the speed the character should move at, this is slower while crouched or prone.
+fn get_movement_speed(
+    character: &Character,
+    config: &CharacterConfig,
+    stance: Option<&CharacterStance>,
+) -> f32 {
+    let stance_multiplier = stance.map_or(1.0, |stance| stance.speed_multiplier(config));
+
+    config.get_movement_speed(character.is_running) * stance_multiplier
+}
+
+/// Returns the velocity the character wants to move at, including the velocity of the platform it stands on.
+fn get_target_velocity(
+    character: &Character,
+    config: &CharacterConfig,
+    stance: Option<&CharacterStance>,
+    platform_carry: Option<&CharacterPlatformCarry>,
+) -> Vec3 {
+    let platform_velocity = platform_carry.map_or(Vec3::ZERO, |carry| carry.platform_velocity());
+
+    character.movement_direction * get_movement_speed(character, config, stance) + platform_velocity
+}
+
+/// Returns the acceleration limited so that it doesn't overshoot the remaining velocity difference in one frame.
+fn limit_acceleration(acceleration: f32, velocity_difference: f32, delta_seconds: f32) -> f32 {
+    match delta_seconds > 0.0 {
+        true => acceleration.min(velocity_difference / delta_seconds),
+        false => acceleration,
+    }
+}
+
 /// Returns the direction aligned with the ground and turned to the characters rotation.
 fn align_direction_to_ground(
     ground_rotation: Quat,
@@ -165,11 +317,50 @@ fn align_direction_to_ground(
     ground_rotation * character_rotation * direction
 }
 
-/// Returns the vector with it's Y component set to 0.
-fn vector_without_y(vector: Vec3) -> Vec3 {
-    Vec3::new(vector.x, 0.0, vector.z)
+/// Returns the direction projected onto the wall plane, keeping its length.
+fn align_direction_to_wall(wall_normal: Direction3d, direction: Vec3) -> Vec3 {
+    let along_wall = vector_without_y(direction - *wall_normal * direction.dot(*wall_normal));
+
+    along_wall.normalize_or_zero() * direction.length()
+}
+
+/// Returns the direction without the part that points up the slope, keeping the part going across or down it.
+fn remove_uphill_component(downhill: Vec3, direction: Vec3) -> Vec3 {
+    direction - downhill * direction.dot(downhill).min(0.0)
 }
 
 fn get_ground_rotation(grounded: Option<&Grounded>) -> Option<Quat> {
     grounded?.ground_rotation()
 }
+
+/// Returns true if the character is idle on a slope it can walk on, and should be kept from sliding down it.
+fn should_hold_in_place(
+    character: &Character,
+    config: &CharacterConfig,
+    grounded: Option<&Grounded>,
+) -> bool {
+    config.prevent_idle_sliding
+        && character.movement_input == Vec3::ZERO
+        && grounded.is_some_and(|grounded| grounded.is_grounded() && !config.is_sliding(grounded))
+}
+
+/// Returns the force that cancels the part of gravity pulling the character along the ground.
+fn get_anti_slide_force(grounded: Option<&Grounded>, gravity: Vec3, mass: f32) -> Vec3 {
+    match grounded.and_then(|grounded| grounded.ground_normal()) {
+        Some(normal) => -(gravity - *normal * gravity.dot(*normal)) * mass,
+        None => Vec3::ZERO,
+    }
+}
+
+/// Returns the downhill direction if the character is sliding down a slope that's too steep to walk on.
+fn get_sliding_downhill_direction(
+    config: &CharacterConfig,
+    grounded: Option<&Grounded>,
+) -> Option<Vec3> {
+    let grounded = grounded?;
+
+    match config.is_sliding(grounded) {
+        true => grounded.downhill_direction(),
+        false => None,
+    }
+}
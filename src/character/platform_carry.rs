@@ -0,0 +1,140 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::grounded::Grounded;
+
+use super::{rotation::apply_character_heading, Character, CharacterSet};
+
+pub struct CharacterPlatformCarryPlugin;
+
+impl Plugin for CharacterPlatformCarryPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            carry_character_on_platform
+                .in_set(CharacterSet::Rotation)
+                .before(apply_character_heading),
+        );
+    }
+}
+
+/// Makes the character move along with the rigid body it's standing on, like elevators, rotating discs or vehicles.
+///
+/// * NOTE: this entity should also have a `Grounded` component.
+#[derive(Component)]
+pub struct CharacterPlatformCarry {
+    /// The velocity of the platform at the point the character stands on, this is kept while in the air.
+    platform_velocity: Vec3,
+}
+
+impl CharacterPlatformCarry {
+    pub fn new() -> Self {
+        Self {
+            platform_velocity: Vec3::ZERO,
+        }
+    }
+
+    /// Returns the velocity of the platform at the point the character stands or last stood on, zero after landing on static ground.
+    ///
+    /// Movement targets are relative to this velocity, so the character can walk around on the platform.
+    pub fn platform_velocity(&self) -> Vec3 {
+        self.platform_velocity
+    }
+}
+
+impl Default for CharacterPlatformCarry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn carry_character_on_platform(
+    mut characters: Query<(
+        &mut CharacterPlatformCarry,
+        &mut Velocity,
+        &mut Character,
+        &Grounded,
+    )>,
+    platforms: Query<
+        (&Velocity, &GlobalTransform, Option<&ReadMassProperties>),
+        Without<CharacterPlatformCarry>,
+    >,
+    rapier_context: Res<RapierContext>,
+    time: Res<Time>,
+) {
+    for (mut carry, mut velocity, mut character, grounded) in characters.iter_mut() {
+        let platform = grounded
+            .ground_entity()
+            .zip(grounded.ground_point())
+            .and_then(|(entity, point)| {
+                Some((
+                    get_platform_motion(entity, &rapier_context, &platforms)?,
+                    point,
+                ))
+            });
+
+        let Some((platform_motion, point)) = platform else {
+            // The platform velocity is remembered while in the air, so landing back on the same platform doesn't add it twice.
+            if grounded.is_grounded() {
+                carry.platform_velocity = Vec3::ZERO;
+            }
+
+            continue;
+        };
+
+        let point_velocity = platform_motion.linvel
+            + platform_motion
+                .angvel
+                .cross(point - platform_motion.center_of_mass);
+
+        // Only add the change in platform velocity, the character already has the rest from previous frames.
+        velocity.linvel += point_velocity - carry.platform_velocity;
+        carry.platform_velocity = point_velocity;
+
+        // The rotation systems turn the body to match the yaw.
+        let yaw = character.yaw() + platform_motion.angvel.y * time.delta_seconds();
+        character.set_yaw(yaw);
+    }
+}
+
+/// The velocity of a platform and the point it rotates around, in world space.
+struct PlatformMotion {
+    linvel: Vec3,
+    angvel: Vec3,
+    center_of_mass: Vec3,
+}
+
+/// Returns the motion of the rigid body, read from rapier so every kind of body works, also ones without a `Velocity` component.
+///
+/// Falls back to the `Velocity` component for entities that rapier doesn't have a body for.
+fn get_platform_motion(
+    entity: Entity,
+    rapier_context: &RapierContext,
+    platforms: &Query<
+        (&Velocity, &GlobalTransform, Option<&ReadMassProperties>),
+        Without<CharacterPlatformCarry>,
+    >,
+) -> Option<PlatformMotion> {
+    let body = rapier_context
+        .entity2body()
+        .get(&entity)
+        .and_then(|handle| rapier_context.bodies.get(*handle));
+
+    if let Some(body) = body {
+        return Some(PlatformMotion {
+            linvel: (*body.linvel()).into(),
+            angvel: (*body.angvel()).into(),
+            center_of_mass: (*body.center_of_mass()).into(),
+        });
+    }
+
+    let (velocity, global_transform, mass_properties) = platforms.get(entity).ok()?;
+
+    Some(PlatformMotion {
+        linvel: velocity.linvel,
+        angvel: velocity.angvel,
+        center_of_mass: global_transform.transform_point(
+            mass_properties.map_or(Vec3::ZERO, |mass| mass.get().local_center_of_mass),
+        ),
+    })
+}
@@ -1,46 +1,106 @@
 use bevy::prelude::*;
 
-use super::{config::CharacterConfig, Character, CharacterHead};
+use super::{config::CharacterConfig, Character, CharacterHead, CharacterSet};
 
 pub struct CharacterRotationPlugin;
 
 impl Plugin for CharacterRotationPlugin {
     fn build(&self, app: &mut App) {
+        // Looking around runs every frame so the camera turns smoothly, the physics step only reads the resulting heading.
         app.add_systems(
             Update,
-            (rotate_character_horizontally, rotate_character_vertically),
-        );
+            (rotate_character_horizontally, rotate_character_vertically)
+                .in_set(CharacterSet::Rotation),
+        )
+        .add_systems(
+            FixedUpdate,
+            apply_character_heading.in_set(CharacterSet::Rotation),
+        )
+        // PreUpdate runs before both the fixed step and Update, so the spawn rotation is read before anything overwrites it.
+        .add_systems(PreUpdate, init_character_yaw);
     }
 }
 
+/// Sets the yaw of new characters from the rotation of their root transform, so a rotated spawn keeps facing the same way.
+fn init_character_yaw(mut characters: Query<(&Transform, &mut Character), Added<Character>>) {
+    for (transform, mut character) in characters.iter_mut() {
+        let (yaw, _, _) = transform.rotation.to_euler(EulerRot::YXZ);
+        character.set_yaw(yaw);
+    }
+}
+
+/// Turns the yaw of the character with the look input, and rotates the root transform to match it.
 fn rotate_character_horizontally(
-    mut characters: Query<(&mut Transform, &Character, &CharacterConfig)>,
+    mut characters: Query<(&mut Transform, &mut Character, &CharacterConfig)>,
+    time: Res<Time>,
 ) {
-    for (mut transform, character, config) in characters
-        .iter_mut()
-        .filter(|(_, character, _)| character.is_active)
-    {
-        transform.rotate_local(Quat::from_axis_angle(
-            Vec3::Y,
-            character.rotation_input.y * config.turn_speed,
-        ));
+    for (mut transform, mut character, config) in characters.iter_mut() {
+        if character.is_active {
+            let rotation = config.look_sensitivity.rotation(
+                character.look_delta,
+                character.look_rate,
+                time.delta_seconds(),
+            );
+
+            // Turning right is a negative rotation around the Y axis.
+            let yaw = character.yaw - rotation.x;
+            character.set_yaw(yaw);
+        }
+
+        character.look_delta.x = 0.0;
+
+        let target_rotation = Quat::from_rotation_y(character.yaw);
+
+        if transform.rotation != target_rotation {
+            transform.rotation = target_rotation;
+        }
     }
 }
 
+/// Rotates the root transform to match the yaw before the physics step, so movement uses the latest heading.
+///
+/// * This runs after `carry_character_on_platform`, so a rotating platform turns the character in the same step.
+pub(crate) fn apply_character_heading(mut characters: Query<(&mut Transform, &Character)>) {
+    for (mut transform, character) in characters.iter_mut() {
+        let target_rotation = Quat::from_rotation_y(character.yaw);
+
+        if transform.rotation != target_rotation {
+            transform.rotation = target_rotation;
+        }
+    }
+}
+
+/// Tilts the pitch of the character with the look input, and rotates the head transform to match it.
 pub fn rotate_character_vertically(
     mut character_heads: Query<(&mut Transform, &Parent), With<CharacterHead>>,
-    characters: Query<(&Character, &CharacterConfig)>,
+    mut characters: Query<(&mut Character, &CharacterConfig)>,
+    time: Res<Time>,
 ) {
     for (mut transform, parent) in character_heads.iter_mut() {
-        if let Ok((character, config)) = characters.get(parent.get()) {
-            if character.is_active {
-                let vertical_angle = transform.rotation.to_scaled_axis().x;
-                let angle_limit_rad = config.vertical_rotation_limit_degrees.to_radians();
-                let new_angle = (vertical_angle + character.rotation_input.x * config.turn_speed)
-                    .clamp(-angle_limit_rad, angle_limit_rad);
-
-                transform.rotation = Quat::from_axis_angle(Vec3::X, new_angle);
-            }
+        let Ok((mut character, config)) = characters.get_mut(parent.get()) else {
+            continue;
+        };
+
+        let mut pitch = character.pitch;
+
+        if character.is_active {
+            let rotation = config.look_sensitivity.rotation(
+                character.look_delta,
+                character.look_rate,
+                time.delta_seconds(),
+            );
+
+            pitch += rotation.y;
+        }
+
+        let (min_pitch, max_pitch) = config.pitch_limits();
+        character.pitch = pitch.clamp(min_pitch, max_pitch);
+        character.look_delta.y = 0.0;
+
+        let target_rotation = Quat::from_rotation_x(character.pitch);
+
+        if transform.rotation != target_rotation {
+            transform.rotation = target_rotation;
         }
     }
 }
@@ -0,0 +1,212 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::grounded::Grounded;
+
+use super::{
+    stance::CharacterStance, vector_without_y, Character, CharacterBody, CharacterEvent,
+    MovementSet,
+};
+
+pub struct CharacterSlidePlugin;
+
+impl Plugin for CharacterSlidePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            (update_slide, apply_slide_velocity, update_body_friction)
+                .chain()
+                .in_set(MovementSet::Slide),
+        );
+    }
+}
+
+/// Lets the character slide along the ground by crouching while running.
+///
+/// While sliding the normal movement forces are turned off, the character keeps its speed with low friction, can only steer slowly and speeds up going down slopes.
+///
+/// * NOTE: this entity should also have `Grounded` and `CharacterStance` components.
+#[derive(Component)]
+pub struct CharacterSlide {
+    /// The minimum horizontal speed needed to start a slide.
+    pub min_start_speed: f32,
+
+    /// The slide ends when the horizontal speed drops below this.
+    pub min_end_speed: f32,
+
+    /// The speed that is added in the sliding direction when a slide starts.
+    pub speed_boost: f32,
+
+    /// The friction coefficient of the body while sliding.
+    pub friction: f32,
+
+    /// How fast the character can change its sliding direction, in degrees per second.
+    pub max_steer_degrees_per_second: f32,
+
+    /// How much of the slope's pull is added on top of gravity when sliding downhill, e.g. 0.5 adds half of it.
+    pub slope_acceleration_multiplier: f32,
+
+    state: Option<SlideState>,
+    had_crouch_input: bool,
+
+    /// The friction the body had before sliding, this is put back when the slide ends.
+    body_friction: Option<Friction>,
+}
+
+#[derive(Clone, Copy)]
+struct SlideState {
+    elapsed: f32,
+}
+
+impl CharacterSlide {
+    pub fn new(min_start_speed: f32, min_end_speed: f32, speed_boost: f32) -> Self {
+        Self {
+            min_start_speed,
+            min_end_speed,
+            speed_boost,
+            friction: 0.02,
+            max_steer_degrees_per_second: 45.0,
+            slope_acceleration_multiplier: 0.5,
+            state: None,
+            had_crouch_input: false,
+            body_friction: None,
+        }
+    }
+
+    pub fn is_sliding(&self) -> bool {
+        self.state.is_some()
+    }
+
+    /// How long the current slide has lasted, in seconds.
+    pub fn elapsed(&self) -> Option<f32> {
+        Some(self.state?.elapsed)
+    }
+}
+
+fn update_slide(
+    mut characters: Query<(
+        &mut CharacterSlide,
+        &mut Velocity,
+        &Character,
+        &CharacterStance,
+        &Grounded,
+        Entity,
+    )>,
+    mut character_events: EventWriter<CharacterEvent>,
+    time: Res<Time>,
+) {
+    for (mut slide, mut velocity, character, stance, grounded, entity) in characters.iter_mut() {
+        let started_crouching = stance.has_crouch_input && !slide.had_crouch_input;
+        slide.had_crouch_input = stance.has_crouch_input;
+
+        let horizontal_velocity = vector_without_y(velocity.linvel);
+
+        match slide.state {
+            Some(mut state) => {
+                state.elapsed += time.delta_seconds();
+
+                let can_continue = character.is_active
+                    && stance.has_crouch_input
+                    && !stance.has_prone_input
+                    && grounded.is_grounded()
+                    && horizontal_velocity.length() >= slide.min_end_speed;
+
+                match can_continue {
+                    true => slide.state = Some(state),
+                    false => {
+                        slide.state = None;
+                        character_events.send(CharacterEvent::SlideEnded { entity });
+                    }
+                }
+            }
+            None => {
+                if character.is_active
+                    && started_crouching
+                    && !stance.has_prone_input
+                    && character.is_running
+                    && grounded.is_grounded()
+                    && horizontal_velocity.length() >= slide.min_start_speed
+                {
+                    slide.state = Some(SlideState { elapsed: 0.0 });
+                    velocity.linvel += horizontal_velocity.normalize_or_zero() * slide.speed_boost;
+                    character_events.send(CharacterEvent::SlideStarted { entity });
+                }
+            }
+        }
+    }
+}
+
+fn apply_slide_velocity(
+    mut characters: Query<(&CharacterSlide, &mut Velocity, &Character, &Grounded)>,
+    rapier_config: Res<RapierConfiguration>,
+    time: Res<Time>,
+) {
+    let gravity = rapier_config.gravity.length();
+
+    for (slide, mut velocity, character, grounded) in characters
+        .iter_mut()
+        .filter(|(slide, _, _, _)| slide.is_sliding())
+    {
+        let horizontal_velocity = vector_without_y(velocity.linvel);
+
+        if let Some(steer_direction) =
+            vector_without_y(character.movement_direction).try_normalize()
+        {
+            let max_angle = slide.max_steer_degrees_per_second.to_radians() * time.delta_seconds();
+            let steered_velocity = steer_towards(horizontal_velocity, steer_direction, max_angle);
+
+            velocity.linvel += steered_velocity - horizontal_velocity;
+        }
+
+        if let (Some(downhill), Some(slope_angle)) = (
+            grounded.downhill_direction(),
+            grounded.slope_angle_degrees(),
+        ) {
+            let slope_pull = gravity * slope_angle.to_radians().sin();
+
+            velocity.linvel +=
+                downhill * slope_pull * slide.slope_acceleration_multiplier * time.delta_seconds();
+        }
+    }
+}
+
+/// Swaps the friction of the body while sliding, and puts the original friction back afterwards.
+fn update_body_friction(
+    mut characters: Query<&mut CharacterSlide>,
+    mut character_bodies: Query<(&mut Friction, &Parent), With<CharacterBody>>,
+) {
+    for (mut friction, parent) in character_bodies.iter_mut() {
+        let Ok(mut slide) = characters.get_mut(parent.get()) else {
+            continue;
+        };
+
+        match (slide.is_sliding(), slide.body_friction) {
+            (true, None) => {
+                slide.body_friction = Some(*friction);
+                *friction = Friction {
+                    coefficient: slide.friction,
+                    combine_rule: CoefficientCombineRule::Min,
+                };
+            }
+            (false, Some(body_friction)) => {
+                slide.body_friction = None;
+                *friction = body_friction;
+            }
+            _ => (),
+        }
+    }
+}
+
+// Utilities
+
+/// Rotates the vector around the Y axis towards the given direction, by at most `max_angle` radians.
+fn steer_towards(vector: Vec3, direction: Vec3, max_angle: f32) -> Vec3 {
+    let Some(current_direction) = vector.try_normalize() else {
+        return vector;
+    };
+
+    let angle = current_direction.angle_between(direction);
+    let sign = current_direction.cross(direction).y.signum();
+
+    Quat::from_rotation_y(angle.min(max_angle) * sign) * vector
+}
@@ -1,16 +1,30 @@
 use bevy::prelude::*;
 use bevy_rapier3d::prelude::*;
 
-use crate::grounded::{CheckMethod, Grounded};
+use crate::{
+    grounded::{CheckMethod, Grounded},
+    wall_contact::WallContact,
+};
 
 use super::{
-    config::CharacterConfig, jump::CharacterJump, Character, CharacterBody, CharacterHead,
+    config::CharacterConfig,
+    dash::CharacterDash,
+    jump::CharacterJump,
+    kinematic::{build_kinematic_controller, CharacterBackend},
+    platform_carry::CharacterPlatformCarry,
+    slide::CharacterSlide,
+    stamina::CharacterStamina,
+    stance::CharacterStance,
+    step_up::CharacterStepUp,
+    wall_run::CharacterWallRun,
+    Character, CharacterBody, CharacterBodyMesh, CharacterHead,
 };
 
 /*
     Entity hierarchy:
     - Character root (character components and rb, on Y: 0)
-        - Character body (capsule with collider)
+        - Character body (capsule collider)
+            - Character body mesh (capsule mesh, scaled when crouched or prone)
         - Character head (cube with collider)
             - First person camera
 */
@@ -18,6 +32,9 @@ use super::{
 pub struct CharacterSpawnSettings {
     pub color: Color,
 
+    /// The physics setup that moves the character, a dynamic rigidbody with forces or a kinematic character controller.
+    pub backend: CharacterBackend,
+
     /// The percentage of the characters height that the head should take up
     pub head_percentage_of_height: f32,
 
@@ -42,6 +59,76 @@ pub struct CharacterSpawnSettings {
     /// Useful when tuning the grounded behaviour
     pub draw_grounded_gizmos: bool,
 
+    /// How long after walking off a ledge the character can still jump, in seconds.
+    pub jump_coyote_time: f32,
+
+    /// How long a jump input is remembered before landing, in seconds.
+    pub jump_buffer_time: f32,
+
+    /// How many extra jumps the character can do while in the air, e.g. set to 1 for a double jump.
+    pub max_air_jumps: u32,
+
+    /// How far from the body the character checks for walls, used for wall jumping.
+    pub wall_check_distance: f32,
+
+    /// The max angle in degrees that a surface can be tilted away from vertical to count as a wall.
+    pub max_wall_tilt_degrees: f32,
+
+    /// If gizmos should be drawn to show the directions used for checking if the character is touching a wall.
+    pub draw_wall_contact_gizmos: bool,
+
+    /// The minimum horizontal speed needed for the character to wall run.
+    pub wall_run_min_speed: f32,
+
+    /// The max time a single wall run can last, in seconds.
+    pub wall_run_max_duration: f32,
+
+    /// What gravity will be multiplied by while wall running.
+    pub wall_run_gravity_multiplier: f32,
+
+    /// The highest step or ledge the character can climb by walking into it, in meters.
+    ///
+    /// * Note: with the kinematic backend this is also the autostep height of the character controller.
+    pub max_step_height: f32,
+
+    /// If gizmos should be drawn to show the rays used for detecting steps.
+    pub draw_step_up_gizmos: bool,
+
+    /// The minimum horizontal speed needed to start a slide by crouching while running.
+    pub slide_min_start_speed: f32,
+
+    /// A slide ends when the horizontal speed drops below this.
+    pub slide_min_end_speed: f32,
+
+    /// The speed that is added when a slide starts.
+    pub slide_speed_boost: f32,
+
+    /// The max stamina of the character, running and jumping use this up.
+    pub max_stamina: f32,
+
+    /// How much stamina running uses per second.
+    pub run_stamina_per_second: f32,
+
+    /// How much stamina a single jump uses.
+    pub jump_stamina_cost: f32,
+
+    /// How far a dash moves the character, in meters.
+    pub dash_distance: f32,
+
+    /// How long a dash lasts, in seconds.
+    pub dash_duration: f32,
+
+    /// How long after a dash the character can dash again, in seconds.
+    pub dash_cooldown: f32,
+
+    /// How many times the character can dash while in the air.
+    pub max_air_dashes: u32,
+
+    /// The mass of the character in kilograms, the colliders have no density so this is the only mass the character has.
+    ///
+    /// * Note: the movement accelerations in `CharacterConfig` are scaled by this mass, so it doesn't change how fast the character speeds up.
+    pub mass: f32,
+
     /// The amount of drag or air resistance this character will experience.
     ///
     /// This is the value that the rigidbody's `linear_damping` will be set to.
@@ -89,12 +176,38 @@ impl Default for CharacterSpawnSettings {
         Self {
             spawn_position: Vec3::ZERO,
             color: Color::CYAN,
+            backend: CharacterBackend::Dynamic,
             size: Vec2::new(0.65, 2.0),
             head_percentage_of_height: 20.0,
             character_name: String::from("Default character"),
-            grounded_height_offset: 0.15,
-            grounded_check_method: CheckMethod::Sphere { radius: 0.2 },
+            grounded_height_offset: 0.25,
+            grounded_check_method: CheckMethod::Sphere {
+                radius: 0.2,
+                distance: 0.1,
+            },
             draw_grounded_gizmos: false,
+            jump_coyote_time: 0.15,
+            jump_buffer_time: 0.15,
+            max_air_jumps: 0,
+            wall_check_distance: 0.1,
+            max_wall_tilt_degrees: 20.0,
+            draw_wall_contact_gizmos: false,
+            wall_run_min_speed: 5.0,
+            wall_run_max_duration: 1.5,
+            wall_run_gravity_multiplier: 0.2,
+            max_step_height: 0.35,
+            draw_step_up_gizmos: false,
+            slide_min_start_speed: 6.0,
+            slide_min_end_speed: 2.0,
+            slide_speed_boost: 2.0,
+            max_stamina: 100.0,
+            run_stamina_per_second: 20.0,
+            jump_stamina_cost: 15.0,
+            dash_distance: 4.0,
+            dash_duration: 0.2,
+            dash_cooldown: 1.0,
+            max_air_dashes: 1,
+            mass: 0.52,
             drag: 0.5,
             friction: 0.3,
         }
@@ -118,6 +231,8 @@ impl CharacterSpawner {
 
     /// Spawns the core character components.
     ///
+    /// The rigidbody depends on the `backend` in the spawn settings.
+    ///
     /// Sets the `root_id` of this spawner.
     pub fn spawn_core(
         &mut self,
@@ -125,18 +240,35 @@ impl CharacterSpawner {
         character: Character,
         character_config: CharacterConfig,
     ) -> &mut Self {
-        let id = commands
-            .spawn((
-                self.build_name_component(String::from("root")),
-                build_rigid_body(self.spawn_settings.drag),
-                TransformBundle::from_transform(Transform::from_translation(
-                    self.spawn_settings.spawn_position,
-                )),
-                VisibilityBundle::default(),
-                character,
-                character_config,
-            ))
-            .id();
+        let mut root = commands.spawn((
+            self.build_name_component(String::from("root")),
+            TransformBundle::from_transform(Transform::from_translation(
+                self.spawn_settings.spawn_position,
+            )),
+            VisibilityBundle::default(),
+        ));
+
+        match self.spawn_settings.backend {
+            CharacterBackend::Dynamic => {
+                root.insert(build_rigid_body(
+                    self.spawn_settings.mass,
+                    self.spawn_settings.drag,
+                ));
+            }
+            CharacterBackend::Kinematic => {
+                root.insert(build_kinematic_rigid_body(
+                    self.spawn_settings.mass,
+                    build_kinematic_controller(
+                        self.spawn_settings.size.y,
+                        self.spawn_settings.radius(),
+                        self.spawn_settings.max_step_height,
+                        &character_config,
+                    ),
+                ));
+            }
+        }
+
+        let id = root.insert((character, character_config)).id();
 
         self.root_id = Some(id);
         self
@@ -158,8 +290,18 @@ impl CharacterSpawner {
                 // Body
                 root.spawn((
                     self.build_name_component(String::from("body")),
-                    build_character_body(meshes, character_material.clone(), &self.spawn_settings),
-                ));
+                    build_character_body(&self.spawn_settings),
+                ))
+                .with_children(|body| {
+                    body.spawn((
+                        self.build_name_component(String::from("body mesh")),
+                        build_character_body_mesh(
+                            meshes,
+                            character_material.clone(),
+                            &self.spawn_settings,
+                        ),
+                    ));
+                });
 
                 // Head
                 let id = root
@@ -189,7 +331,132 @@ impl CharacterSpawner {
                     self.spawn_settings.grounded_check_method,
                     self.spawn_settings.draw_grounded_gizmos,
                 ),
-                CharacterJump::new(),
+                CharacterJump::new(
+                    self.spawn_settings.jump_coyote_time,
+                    self.spawn_settings.jump_buffer_time,
+                    self.spawn_settings.max_air_jumps,
+                ),
+            ));
+        }
+        self
+    }
+
+    /// Spawns a `WallContact` component on the character root entity, this allows the character to wall jump.
+    ///
+    /// Requires the `root_id` to be set, do this with the `spawn_core` function.
+    pub fn add_wall_contact(&mut self, commands: &mut Commands) -> &mut Self {
+        if let Some(root_id) = self.root_id {
+            commands.entity(root_id).insert(WallContact::new(
+                self.spawn_settings.half_body_height(),
+                self.spawn_settings.radius(),
+                self.spawn_settings.wall_check_distance,
+                self.spawn_settings.max_wall_tilt_degrees,
+                self.spawn_settings.draw_wall_contact_gizmos,
+            ));
+        }
+        self
+    }
+
+    /// Spawns a `CharacterWallRun` component on the character root entity.
+    ///
+    /// Requires the `root_id` to be set, do this with the `spawn_core` function.
+    /// Also requires the `Grounded` and `WallContact` components, add these with the `add_jumping` and `add_wall_contact` functions.
+    pub fn add_wall_running(&mut self, commands: &mut Commands) -> &mut Self {
+        if let Some(root_id) = self.root_id {
+            commands.entity(root_id).insert(CharacterWallRun::new(
+                self.spawn_settings.wall_run_min_speed,
+                self.spawn_settings.wall_run_max_duration,
+                self.spawn_settings.wall_run_gravity_multiplier,
+            ));
+        }
+        self
+    }
+
+    /// Spawns a `CharacterStepUp` component on the character root entity, this lets the character climb stairs.
+    ///
+    /// Requires the `root_id` to be set, do this with the `spawn_core` function.
+    /// Also requires the `Grounded` component, add this with the `add_jumping` function.
+    pub fn add_step_up(&mut self, commands: &mut Commands) -> &mut Self {
+        if let Some(root_id) = self.root_id {
+            commands.entity(root_id).insert(CharacterStepUp::new(
+                self.spawn_settings.max_step_height,
+                self.spawn_settings.radius(),
+                self.spawn_settings.draw_step_up_gizmos,
+            ));
+        }
+        self
+    }
+
+    /// Spawns a `CharacterPlatformCarry` component on the character root entity, this makes the character move along with the platform it stands on.
+    ///
+    /// Requires the `root_id` to be set, do this with the `spawn_core` function.
+    /// Also requires the `Grounded` component, add this with the `add_jumping` function.
+    pub fn add_platform_carry(&mut self, commands: &mut Commands) -> &mut Self {
+        if let Some(root_id) = self.root_id {
+            commands
+                .entity(root_id)
+                .insert(CharacterPlatformCarry::new());
+        }
+        self
+    }
+
+    /// Spawns a `CharacterStance` component on the character root entity, this lets the character crouch and go prone.
+    ///
+    /// The standing sizes are taken from the spawn settings, the crouched and prone heights from the `CharacterConfig`.
+    ///
+    /// Requires the `root_id` to be set, do this with the `spawn_core` function.
+    /// Also requires the body, add this with the `add_body` function.
+    pub fn add_stances(&mut self, commands: &mut Commands) -> &mut Self {
+        if let Some(root_id) = self.root_id {
+            commands.entity(root_id).insert(CharacterStance::new(
+                self.spawn_settings.body_height(),
+                self.spawn_settings.head_height(),
+                self.spawn_settings.radius(),
+            ));
+        }
+        self
+    }
+
+    /// Spawns a `CharacterSlide` component on the character root entity, this lets the character slide by crouching while running.
+    ///
+    /// Requires the `root_id` to be set, do this with the `spawn_core` function.
+    /// Also requires the `Grounded` and `CharacterStance` components, add these with the `add_jumping` and `add_stances` functions.
+    pub fn add_sliding(&mut self, commands: &mut Commands) -> &mut Self {
+        if let Some(root_id) = self.root_id {
+            commands.entity(root_id).insert(CharacterSlide::new(
+                self.spawn_settings.slide_min_start_speed,
+                self.spawn_settings.slide_min_end_speed,
+                self.spawn_settings.slide_speed_boost,
+            ));
+        }
+        self
+    }
+
+    /// Spawns a `CharacterStamina` component on the character root entity, this limits how long the character can run and how often it can jump.
+    ///
+    /// Requires the `root_id` to be set, do this with the `spawn_core` function.
+    pub fn add_stamina(&mut self, commands: &mut Commands) -> &mut Self {
+        if let Some(root_id) = self.root_id {
+            commands.entity(root_id).insert(CharacterStamina::new(
+                self.spawn_settings.max_stamina,
+                self.spawn_settings.run_stamina_per_second,
+                self.spawn_settings.jump_stamina_cost,
+            ));
+        }
+        self
+    }
+
+    /// Spawns a `CharacterDash` component on the character root entity, this lets the character dash.
+    ///
+    /// Requires the `root_id` to be set, do this with the `spawn_core` function.
+    /// Air dashes are only limited if the character has a `Grounded` component, add this with the `add_jumping` function.
+    pub fn add_dashing(&mut self, commands: &mut Commands) -> &mut Self {
+        if let Some(root_id) = self.root_id {
+            commands.entity(root_id).insert(CharacterDash::new(
+                self.spawn_settings.dash_distance,
+                self.spawn_settings.dash_duration,
+                self.spawn_settings.dash_cooldown,
+                self.spawn_settings.max_air_dashes,
             ));
         }
         self
@@ -238,18 +505,23 @@ impl CharacterSpawner {
 /// Returns a dynamic rigidbody with relevant components for characters
 ///
 /// * Linear damping simulates the strength of air resistance
+/// * The mass is set here instead of coming from the colliders, so resizing the body doesn't change it
 fn build_rigid_body(
+    mass: f32,
     linear_damping: f32,
 ) -> (
     RigidBody,
+    AdditionalMassProperties,
     Velocity,
     LockedAxes,
     ExternalForce,
     ExternalImpulse,
     Damping,
+    ReadMassProperties,
 ) {
     (
         RigidBody::Dynamic,
+        AdditionalMassProperties::Mass(mass),
         Velocity::default(),
         LockedAxes::ROTATION_LOCKED,
         ExternalForce::default(),
@@ -258,15 +530,72 @@ fn build_rigid_body(
             linear_damping,
             angular_damping: 0.0,
         },
+        ReadMassProperties::default(),
+    )
+}
+
+/// Returns a kinematic rigidbody that is moved by a `KinematicCharacterController`
+///
+/// * The forces and mass are only there so the same systems can run on both backends, they don't move a kinematic body
+fn build_kinematic_rigid_body(
+    mass: f32,
+    controller: KinematicCharacterController,
+) -> (
+    RigidBody,
+    AdditionalMassProperties,
+    Velocity,
+    LockedAxes,
+    ExternalForce,
+    ExternalImpulse,
+    ReadMassProperties,
+    KinematicCharacterController,
+) {
+    (
+        RigidBody::KinematicPositionBased,
+        AdditionalMassProperties::Mass(mass),
+        Velocity::default(),
+        LockedAxes::ROTATION_LOCKED,
+        ExternalForce::default(),
+        ExternalImpulse::default(),
+        ReadMassProperties::default(),
+        controller,
     )
 }
 
-/// Returns a capsule with collider that has the given size and color
+/// Returns a capsule collider that has the given size
 fn build_character_body(
+    spawn_settings: &CharacterSpawnSettings,
+) -> (
+    TransformBundle,
+    VisibilityBundle,
+    Collider,
+    ColliderMassProperties,
+    Friction,
+    CharacterBody,
+) {
+    (
+        TransformBundle::from_transform(Transform::from_xyz(
+            0.0,
+            spawn_settings.half_body_height(),
+            0.0,
+        )),
+        VisibilityBundle::default(),
+        Collider::capsule_y(
+            spawn_settings.straight_height() / 2.0,
+            spawn_settings.radius(),
+        ),
+        ColliderMassProperties::Density(0.0),
+        Friction::coefficient(spawn_settings.friction),
+        CharacterBody,
+    )
+}
+
+/// Returns a capsule mesh that has the given size and color
+fn build_character_body_mesh(
     meshes: &mut ResMut<Assets<Mesh>>,
     material_handle: Handle<StandardMaterial>,
     spawn_settings: &CharacterSpawnSettings,
-) -> (PbrBundle, Collider, Friction, CharacterBody) {
+) -> (PbrBundle, CharacterBodyMesh) {
     (
         PbrBundle {
             mesh: meshes.add(Capsule3d::new(
@@ -274,15 +603,9 @@ fn build_character_body(
                 spawn_settings.straight_height(),
             )),
             material: material_handle,
-            transform: Transform::from_xyz(0.0, spawn_settings.half_body_height(), 0.0),
             ..default()
         },
-        Collider::capsule_y(
-            spawn_settings.straight_height() / 2.0,
-            spawn_settings.radius(),
-        ),
-        Friction::coefficient(spawn_settings.friction),
-        CharacterBody,
+        CharacterBodyMesh,
     )
 }
 
@@ -290,7 +613,7 @@ fn build_character_head(
     meshes: &mut ResMut<Assets<Mesh>>,
     material_handle: Handle<StandardMaterial>,
     spawn_settings: &CharacterSpawnSettings,
-) -> (PbrBundle, Collider, CharacterHead) {
+) -> (PbrBundle, Collider, ColliderMassProperties, CharacterHead) {
     let head_size = spawn_settings.head_height();
     (
         PbrBundle {
@@ -304,6 +627,7 @@ fn build_character_head(
             ..default()
         },
         Collider::cuboid(head_size / 2.0, head_size / 2.0, head_size / 2.0),
+        ColliderMassProperties::Density(0.0),
         CharacterHead,
     )
 }
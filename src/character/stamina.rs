@@ -0,0 +1,170 @@
+use bevy::prelude::*;
+
+use super::{Character, CharacterEvent, MovementSet};
+
+pub struct CharacterStaminaPlugin;
+
+impl Plugin for CharacterStaminaPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<StaminaEvent>().add_systems(
+            FixedUpdate,
+            (update_stamina, stop_running_if_exhausted)
+                .chain()
+                .in_set(MovementSet::State),
+        );
+    }
+}
+
+/// Limits how long the character can run and how often it can jump.
+///
+/// Running and jumping use up stamina, when it runs out the character is exhausted. It can't run or jump until enough stamina has come back.
+#[derive(Component)]
+pub struct CharacterStamina {
+    pub max_stamina: f32,
+
+    /// How much stamina running uses per second.
+    pub run_drain_per_second: f32,
+
+    /// How much stamina a single jump uses.
+    pub jump_cost: f32,
+
+    /// How long after using stamina it starts coming back, in seconds.
+    pub regen_delay: f32,
+
+    /// How much stamina comes back per second.
+    pub regen_per_second: f32,
+
+    /// How much stamina is needed to stop being exhausted.
+    pub recovery_threshold: f32,
+
+    stamina: f32,
+    time_since_drain: f32,
+    is_exhausted: bool,
+}
+
+impl CharacterStamina {
+    pub fn new(max_stamina: f32, run_drain_per_second: f32, jump_cost: f32) -> Self {
+        Self {
+            max_stamina,
+            run_drain_per_second,
+            jump_cost,
+            regen_delay: 1.0,
+            regen_per_second: max_stamina / 4.0,
+            recovery_threshold: max_stamina / 4.0,
+            stamina: max_stamina,
+            time_since_drain: 0.0,
+            is_exhausted: false,
+        }
+    }
+
+    pub fn stamina(&self) -> f32 {
+        self.stamina
+    }
+
+    /// Returns the current stamina as a fraction of the max, between 0 and 1.
+    pub fn fraction(&self) -> f32 {
+        match self.max_stamina > 0.0 {
+            true => self.stamina / self.max_stamina,
+            false => 0.0,
+        }
+    }
+
+    /// Returns true if the character ran out of stamina and hasn't recovered enough yet, it can't run or jump while exhausted.
+    pub fn is_exhausted(&self) -> bool {
+        self.is_exhausted
+    }
+
+    fn drain(&mut self, amount: f32) {
+        self.stamina = (self.stamina - amount).max(0.0);
+        self.time_since_drain = 0.0;
+    }
+
+    fn regenerate(&mut self, delta_seconds: f32) {
+        self.time_since_drain += delta_seconds;
+
+        if self.time_since_drain >= self.regen_delay {
+            self.stamina =
+                (self.stamina + self.regen_per_second * delta_seconds).min(self.max_stamina);
+        }
+    }
+}
+
+/// Events sent when the stamina of a character changes, e.g. to update a stamina bar.
+#[derive(Event, Clone, Copy, Debug)]
+pub enum StaminaEvent {
+    Changed {
+        entity: Entity,
+        stamina: f32,
+        max_stamina: f32,
+    },
+    Exhausted {
+        entity: Entity,
+    },
+    Recovered {
+        entity: Entity,
+    },
+}
+
+fn update_stamina(
+    mut characters: Query<(&mut CharacterStamina, &Character, Entity)>,
+    mut character_events: EventReader<CharacterEvent>,
+    mut stamina_events: EventWriter<StaminaEvent>,
+    time: Res<Time>,
+) {
+    let jumped_entities: Vec<Entity> = character_events
+        .read()
+        .filter_map(|event| match event {
+            CharacterEvent::Jumped { entity } => Some(*entity),
+            _ => None,
+        })
+        .collect();
+
+    for (mut stamina, character, entity) in characters.iter_mut() {
+        let previous_stamina = stamina.stamina;
+        let is_running =
+            character.is_running && character.movement_input != Vec3::ZERO && !stamina.is_exhausted;
+        let jump_count = jumped_entities
+            .iter()
+            .filter(|jumped| **jumped == entity)
+            .count();
+
+        if is_running {
+            let amount = stamina.run_drain_per_second * time.delta_seconds();
+            stamina.drain(amount);
+        }
+
+        if jump_count > 0 {
+            let amount = stamina.jump_cost * jump_count as f32;
+            stamina.drain(amount);
+        }
+
+        if !is_running && jump_count == 0 {
+            stamina.regenerate(time.delta_seconds());
+        }
+
+        if stamina.stamina != previous_stamina {
+            stamina_events.send(StaminaEvent::Changed {
+                entity,
+                stamina: stamina.stamina,
+                max_stamina: stamina.max_stamina,
+            });
+        }
+
+        if !stamina.is_exhausted && stamina.stamina <= 0.0 {
+            stamina.is_exhausted = true;
+            stamina_events.send(StaminaEvent::Exhausted { entity });
+        } else if stamina.is_exhausted && stamina.stamina >= stamina.recovery_threshold {
+            stamina.is_exhausted = false;
+            stamina_events.send(StaminaEvent::Recovered { entity });
+        }
+    }
+}
+
+fn stop_running_if_exhausted(mut characters: Query<(&mut Character, &CharacterStamina)>) {
+    for (mut character, _) in characters
+        .iter_mut()
+        .filter(|(character, stamina)| character.is_running && stamina.is_exhausted())
+    {
+        character.is_running = false;
+    }
+}
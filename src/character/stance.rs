@@ -0,0 +1,564 @@
+use std::f32::consts::FRAC_PI_2;
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use super::{
+    config::CharacterConfig, kinematic::build_controller_shape, Character, CharacterBody,
+    CharacterBodyMesh, CharacterEvent, CharacterHead, MovementSet,
+};
+
+pub struct CharacterStancePlugin;
+
+impl Plugin for CharacterStancePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            (
+                update_stance,
+                update_height,
+                (
+                    update_body_height,
+                    update_head_height,
+                    update_controller_shape,
+                ),
+            )
+                .chain()
+                .in_set(MovementSet::State),
+        );
+    }
+}
+
+/// Lets the character crouch and go prone.
+///
+/// Crouching shrinks the body from the top down so the feet stay on the ground. Going prone lays the body down along the facing direction, with the head at the front.
+///
+/// * Set `has_crouch_input` or `has_prone_input` to true while the character should be in that stance, prone wins if both are set.
+/// * The height, transition speed and move speed of each stance are set in the `CharacterConfig`, the move speed blends between stances with the height.
+/// * The character only gets taller when there is enough room above it, otherwise it stays in the highest stance that fits.
+/// * The character only goes prone when there is enough room to lie down, otherwise it keeps its current stance.
+/// * While prone the character can't turn further when the body would swing into something.
+/// * NOTE: it is recommended to use the `add_stances` function of `CharacterSpawner`, this sets the standing sizes.
+#[derive(Component)]
+pub struct CharacterStance {
+    pub has_crouch_input: bool,
+    pub has_prone_input: bool,
+
+    /// How much narrower the ceiling check is than the body, so walls right next to the character don't keep it low.
+    pub ceiling_check_margin: f32,
+
+    /// The stance the character is in or moving towards.
+    stance: Stance,
+
+    /// The current total height of the character, this moves towards the height of `stance`.
+    height: f32,
+
+    /// If there is enough room above the character to stand up fully.
+    can_stand: bool,
+
+    /// If there is enough room above the character to crouch.
+    can_crouch: bool,
+
+    /// If there is enough room around the character to lie down.
+    can_lie_down: bool,
+
+    /// The last yaw with enough room to lie down, turning while prone goes back to this when the body would hit something.
+    prone_yaw: f32,
+
+    /// The height of the body when standing, without the head.
+    standing_body_height: f32,
+    head_height: f32,
+    radius: f32,
+}
+
+/// The shape and placement of the body collider, the capsule's straight section is along the Y axis of `rotation`.
+#[derive(Clone, Copy, PartialEq)]
+struct BodyCapsule {
+    center: Vec3,
+    rotation: Quat,
+    half_straight_height: f32,
+    radius: f32,
+}
+
+impl BodyCapsule {
+    fn collider(&self) -> Collider {
+        Collider::capsule_y(self.half_straight_height, self.radius)
+    }
+
+    /// The total length of the capsule, including the rounded ends.
+    fn length(&self) -> f32 {
+        (self.half_straight_height + self.radius) * 2.0
+    }
+}
+
+/// The stances a character can be in, from tallest to lowest.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Stance {
+    #[default]
+    Standing,
+    Crouched,
+    Prone,
+}
+
+impl Stance {
+    /// Returns this stance followed by the stances lower than it.
+    fn this_and_lower(&self) -> &'static [Stance] {
+        match self {
+            Stance::Standing => &[Stance::Standing, Stance::Crouched, Stance::Prone],
+            Stance::Crouched => &[Stance::Crouched, Stance::Prone],
+            Stance::Prone => &[Stance::Prone],
+        }
+    }
+}
+
+impl CharacterStance {
+    pub fn new(standing_body_height: f32, head_height: f32, radius: f32) -> Self {
+        Self {
+            has_crouch_input: false,
+            has_prone_input: false,
+            ceiling_check_margin: 0.05,
+            stance: Stance::Standing,
+            height: standing_body_height + head_height,
+            can_stand: true,
+            can_crouch: true,
+            can_lie_down: true,
+            prone_yaw: 0.0,
+            standing_body_height,
+            head_height,
+            radius,
+        }
+    }
+
+    /// Toggles the `has_crouch_input` field and returns the new value
+    pub fn toggle_crouch_input(&mut self) -> bool {
+        self.has_crouch_input = !self.has_crouch_input;
+        self.has_crouch_input
+    }
+
+    /// Toggles the `has_prone_input` field and returns the new value
+    pub fn toggle_prone_input(&mut self) -> bool {
+        self.has_prone_input = !self.has_prone_input;
+        self.has_prone_input
+    }
+
+    /// Returns the stance the character is in or moving towards.
+    pub fn stance(&self) -> Stance {
+        self.stance
+    }
+
+    /// Returns true if the character is crouched or moving into a crouch.
+    pub fn is_crouching(&self) -> bool {
+        self.stance == Stance::Crouched
+    }
+
+    /// Returns true if the character is prone or moving into prone.
+    pub fn is_prone(&self) -> bool {
+        self.stance == Stance::Prone
+    }
+
+    /// Returns true if there is enough room above the character to stand up fully.
+    pub fn can_stand(&self) -> bool {
+        self.can_stand
+    }
+
+    /// Returns true if there is enough room above the character to crouch.
+    pub fn can_crouch(&self) -> bool {
+        self.can_crouch
+    }
+
+    /// Returns true if there is enough room around the character to lie down.
+    pub fn can_lie_down(&self) -> bool {
+        self.can_lie_down
+    }
+
+    /// Returns the current total height of the character, including the head.
+    pub fn height(&self) -> f32 {
+        self.height
+    }
+
+    /// Returns what the movement speed should be multiplied by at the current height.
+    ///
+    /// This blends between the multipliers of the stances above and below the current height, so the speed changes along with the height.
+    pub fn speed_multiplier(&self, config: &CharacterConfig) -> f32 {
+        let standing_height = self.stance_height(Stance::Standing, config);
+        let crouch_height = self.stance_height(Stance::Crouched, config);
+        let prone_height = self.stance_height(Stance::Prone, config);
+
+        match self.height >= crouch_height {
+            true => config.crouch_speed_multiplier.lerp(
+                1.0,
+                fraction_between(self.height, crouch_height, standing_height),
+            ),
+            false => config.prone_speed_multiplier.lerp(
+                config.crouch_speed_multiplier,
+                fraction_between(self.height, prone_height, crouch_height),
+            ),
+        }
+    }
+
+    /// Returns the total height of the character in the given stance, including the head.
+    pub fn stance_height(&self, stance: Stance, config: &CharacterConfig) -> f32 {
+        match stance {
+            Stance::Standing => self.standing_body_height + self.head_height,
+            Stance::Crouched => config.crouch_height.max(self.min_height()),
+            Stance::Prone => config.prone_height.max(self.head_height),
+        }
+    }
+
+    /// The stance that the inputs ask for.
+    fn desired_stance(&self) -> Stance {
+        match (self.has_prone_input, self.has_crouch_input) {
+            (true, _) => Stance::Prone,
+            (false, true) => Stance::Crouched,
+            (false, false) => Stance::Standing,
+        }
+    }
+
+    /// Returns the current height of the body, without the head.
+    ///
+    /// The body can't get shorter than its width, the capsule would have no straight section left.
+    fn body_height(&self) -> f32 {
+        (self.height - self.head_height).max(self.radius * 2.0)
+    }
+
+    /// The lowest the character can get while upright, the head sinks into the body once the body can't shrink further.
+    fn min_height(&self) -> f32 {
+        (self.radius * 2.0).max(self.head_height)
+    }
+
+    /// Returns the capsule of the body collider at the current height, relative to the character root.
+    ///
+    /// Upright the capsule reaches from the feet to the bottom of the head. Below the crouch height it tips forward and changes towards the prone capsule, so it follows the height while lying down and getting up.
+    fn body_capsule(&self, config: &CharacterConfig) -> BodyCapsule {
+        let body_height = self.body_height();
+        let upright = BodyCapsule {
+            center: Vec3::Y * body_height / 2.0,
+            rotation: Quat::IDENTITY,
+            half_straight_height: (body_height / 2.0 - self.radius).max(0.0),
+            radius: self.radius,
+        };
+
+        let prone_fraction = self.prone_fraction(config);
+
+        if prone_fraction <= 0.0 {
+            return upright;
+        }
+
+        let prone = self.prone_capsule(config);
+        let half_straight_height = upright
+            .half_straight_height
+            .lerp(prone.half_straight_height, prone_fraction);
+        let radius = upright.radius.lerp(prone.radius, prone_fraction);
+        let angle = FRAC_PI_2 * prone_fraction;
+
+        // Raised so the lowest point of the tilted capsule stays at the feet.
+        BodyCapsule {
+            center: Vec3::Y * (half_straight_height * angle.cos() + radius),
+            rotation: Quat::from_rotation_x(-angle),
+            half_straight_height,
+            radius,
+        }
+    }
+
+    fn prone_capsule(&self, config: &CharacterConfig) -> BodyCapsule {
+        let radius = self.stance_height(Stance::Prone, config) / 2.0;
+
+        BodyCapsule {
+            center: Vec3::Y * radius,
+            rotation: Quat::from_rotation_x(-FRAC_PI_2),
+            half_straight_height: (config.prone_length / 2.0 - radius).max(0.0),
+            radius,
+        }
+    }
+
+    /// Returns how far the character is from the crouch height to the prone height, from 0 to 1.
+    fn prone_fraction(&self, config: &CharacterConfig) -> f32 {
+        1.0 - fraction_between(
+            self.height,
+            self.stance_height(Stance::Prone, config),
+            self.stance_height(Stance::Crouched, config),
+        )
+    }
+
+    /// Returns how far forward the head is, it moves to the front of the body while getting lower than a crouch.
+    fn head_forward_offset(&self, config: &CharacterConfig) -> f32 {
+        self.prone_fraction(config) * (config.prone_length - self.head_height).max(0.0) / 2.0
+    }
+
+    fn ceiling_check_radius(&self) -> f32 {
+        (self.radius - self.ceiling_check_margin).max(0.01)
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn update_stance(
+    mut characters: Query<(
+        &mut CharacterStance,
+        &mut Character,
+        &mut Transform,
+        &CharacterConfig,
+        &GlobalTransform,
+        Entity,
+    )>,
+    mut character_events: EventWriter<CharacterEvent>,
+    rapier_context: Res<RapierContext>,
+) {
+    for (mut stance, mut character, mut transform, config, global_transform, entity) in
+        characters.iter_mut()
+    {
+        let has_room_for = |target: Stance| {
+            has_room_for_height(
+                &rapier_context,
+                &stance,
+                stance.stance_height(target, config),
+                global_transform,
+                entity,
+            )
+        };
+
+        let can_stand = has_room_for(Stance::Standing);
+        let can_crouch = has_room_for(Stance::Crouched);
+        let has_room_to_lie_down_at = |yaw: f32| {
+            has_room_to_lie_down(
+                &rapier_context,
+                &stance,
+                config,
+                global_transform.translation(),
+                yaw,
+                entity,
+            )
+        };
+
+        let yaw = character.yaw();
+        let mut can_lie_down = has_room_to_lie_down_at(yaw);
+
+        // Turning swings the lying body around, so a turn that would push it into something is undone.
+        if stance.is_prone()
+            && !can_lie_down
+            && yaw != stance.prone_yaw
+            && has_room_to_lie_down_at(stance.prone_yaw)
+        {
+            character.set_yaw(stance.prone_yaw);
+            transform.rotation = Quat::from_rotation_y(character.yaw());
+            can_lie_down = true;
+        }
+
+        if can_lie_down {
+            stance.prone_yaw = character.yaw();
+        }
+
+        // Getting taller needs room above the character, lying down needs room around it. Without room for any of them the stance stays the same.
+        let new_stance = stance
+            .desired_stance()
+            .this_and_lower()
+            .iter()
+            .copied()
+            .find(|target| match target {
+                Stance::Standing => can_stand,
+                Stance::Crouched => can_crouch,
+                Stance::Prone => can_lie_down,
+            })
+            .unwrap_or(stance.stance);
+
+        stance.can_stand = can_stand;
+        stance.can_crouch = can_crouch;
+        stance.can_lie_down = can_lie_down;
+
+        if new_stance != stance.stance {
+            stance.stance = new_stance;
+
+            character_events.send(match new_stance {
+                Stance::Standing => CharacterEvent::Stood { entity },
+                Stance::Crouched => CharacterEvent::Crouched { entity },
+                Stance::Prone => CharacterEvent::WentProne { entity },
+            });
+        }
+    }
+}
+
+/// Moves the height of the character towards the height of its stance.
+fn update_height(mut characters: Query<(&mut CharacterStance, &CharacterConfig)>, time: Res<Time>) {
+    for (mut stance, config) in characters.iter_mut() {
+        let target_height = stance.stance_height(stance.stance, config);
+        let max_change = config.stance_transition_speed * time.delta_seconds();
+        let difference = target_height - stance.height;
+
+        if difference != 0.0 {
+            stance.height += difference.clamp(-max_change, max_change);
+        }
+    }
+}
+
+/// Casts a sphere up from the top of the character, over the height it would grow to reach the given height.
+///
+/// Returns true without casting if the character is already at least this tall.
+fn has_room_for_height(
+    rapier_context: &RapierContext,
+    stance: &CharacterStance,
+    height: f32,
+    global_transform: &GlobalTransform,
+    entity: Entity,
+) -> bool {
+    if height <= stance.height {
+        return true;
+    }
+
+    let radius = stance.ceiling_check_radius();
+    let origin = global_transform.translation() + Vec3::Y * (stance.height - radius);
+
+    rapier_context
+        .cast_shape(
+            origin,
+            Quat::IDENTITY,
+            Vec3::Y,
+            &Collider::ball(radius),
+            ShapeCastOptions::with_max_time_of_impact(height - stance.height),
+            QueryFilter::default().exclude_rigid_body(entity),
+        )
+        .is_none()
+}
+
+/// Checks if the prone body fits around the character when facing the given yaw, with the body lifted and thinned by `ceiling_check_margin` so the ground doesn't count.
+fn has_room_to_lie_down(
+    rapier_context: &RapierContext,
+    stance: &CharacterStance,
+    config: &CharacterConfig,
+    root_position: Vec3,
+    yaw: f32,
+    entity: Entity,
+) -> bool {
+    let capsule = stance.prone_capsule(config);
+    let root_rotation = Quat::from_rotation_y(yaw);
+    let shape = Collider::capsule_y(
+        capsule.half_straight_height,
+        (capsule.radius - stance.ceiling_check_margin).max(0.01),
+    );
+
+    rapier_context
+        .intersection_with_shape(
+            root_position + root_rotation * capsule.center + Vec3::Y * stance.ceiling_check_margin,
+            root_rotation * capsule.rotation,
+            &shape,
+            QueryFilter::default().exclude_rigid_body(entity),
+        )
+        .is_none()
+}
+
+/// Resizes and turns the body collider and the body mesh, the bottom of the body stays at the character's feet.
+///
+/// The colliders have no density, so resizing them doesn't change the character's mass.
+#[allow(clippy::type_complexity)]
+fn update_body_height(
+    characters: Query<(&CharacterStance, &CharacterConfig)>,
+    mut character_bodies: Query<
+        (&mut Collider, &mut Transform, &Parent, Entity),
+        With<CharacterBody>,
+    >,
+    mut body_meshes: Query<
+        (&mut Transform, &Parent),
+        (With<CharacterBodyMesh>, Without<CharacterBody>),
+    >,
+) {
+    for (mut collider, mut transform, parent, body_entity) in character_bodies.iter_mut() {
+        let Ok((stance, config)) = characters.get(parent.get()) else {
+            continue;
+        };
+
+        let capsule = stance.body_capsule(config);
+
+        if transform.translation == capsule.center && transform.rotation == capsule.rotation {
+            continue;
+        }
+
+        transform.translation = capsule.center;
+        transform.rotation = capsule.rotation;
+
+        if let Some(mut collider_capsule) = collider.as_capsule_mut() {
+            collider_capsule.set_segment(
+                Vec3::NEG_Y * capsule.half_straight_height,
+                Vec3::Y * capsule.half_straight_height,
+            );
+            collider_capsule.set_radius(capsule.radius);
+        }
+
+        // The mesh is made at the standing size, so it's scaled relative to that.
+        for (mut mesh_transform, mesh_parent) in body_meshes.iter_mut() {
+            if mesh_parent.get() == body_entity {
+                let width_scale = capsule.radius / stance.radius;
+                mesh_transform.scale = Vec3::new(
+                    width_scale,
+                    capsule.length() / stance.standing_body_height,
+                    width_scale,
+                );
+            }
+        }
+    }
+}
+
+/// Moves the head so its top stays at the top of the character, and to the front of the body when going prone.
+fn update_head_height(
+    characters: Query<(&CharacterStance, &CharacterConfig)>,
+    mut character_heads: Query<(&mut Transform, &Parent), With<CharacterHead>>,
+) {
+    for (mut transform, parent) in character_heads.iter_mut() {
+        if let Ok((stance, config)) = characters.get(parent.get()) {
+            let target_translation = Vec3::new(
+                0.0,
+                stance.height - stance.head_height / 2.0,
+                -stance.head_forward_offset(config),
+            );
+
+            if transform.translation != target_translation {
+                transform.translation = target_translation;
+            }
+        }
+    }
+}
+
+/// Resizes the shape of the `KinematicCharacterController` to the current height, for characters that use the kinematic backend.
+///
+/// Upright the shape covers the body and head, below the crouch height it's the body capsule tipping over to lie down.
+fn update_controller_shape(
+    mut characters: Query<(
+        &mut KinematicCharacterController,
+        &CharacterStance,
+        &CharacterConfig,
+    )>,
+) {
+    for (mut controller, stance, config) in characters.iter_mut() {
+        let is_upright = stance.prone_fraction(config) <= 0.0;
+        let capsule = stance.body_capsule(config);
+
+        let (target_offset, target_rotation) = match is_upright {
+            true => (Vec3::Y * stance.height / 2.0, Quat::IDENTITY),
+            false => (capsule.center, capsule.rotation),
+        };
+
+        let is_up_to_date =
+            controller
+                .custom_shape
+                .as_ref()
+                .is_some_and(|(_, offset, rotation)| {
+                    *offset == target_offset && *rotation == target_rotation
+                });
+
+        if is_up_to_date {
+            continue;
+        }
+
+        controller.custom_shape = Some(match is_upright {
+            true => build_controller_shape(stance.height, stance.radius),
+            false => (capsule.collider(), capsule.center, capsule.rotation),
+        });
+    }
+}
+
+// Utilities
+
+/// Returns where the value is between `low` and `high`, from 0 to 1.
+fn fraction_between(value: f32, low: f32, high: f32) -> f32 {
+    match high > low {
+        true => ((value - low) / (high - low)).clamp(0.0, 1.0),
+        false => 1.0,
+    }
+}
@@ -0,0 +1,242 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::grounded::Grounded;
+
+use super::{Character, MovementSet};
+
+pub struct CharacterStepUpPlugin;
+
+impl Plugin for CharacterStepUpPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(FixedUpdate, step_up_character.in_set(MovementSet::StepUp))
+            .add_systems(Update, draw_step_up_gizmos);
+    }
+}
+
+/// Lets the character climb stairs and small ledges by lifting it over obstacles in front of its feet.
+///
+/// * NOTE: this entity should also have a `Grounded` component.
+/// * This does nothing for characters with the kinematic backend, the character controller steps up by itself.
+#[derive(Component)]
+pub struct CharacterStepUp {
+    /// The highest step the character can climb, in meters.
+    pub max_step_height: f32,
+
+    /// Steps lower than this are ignored, in meters.
+    pub min_step_height: f32,
+
+    /// How far in front of the body the character looks for steps, in meters.
+    pub check_distance: f32,
+
+    /// The minimum angle in degrees from horizontal that the front of an obstacle needs to have to count as a step.
+    ///
+    /// This keeps the character from hopping when walking up slopes.
+    pub min_riser_angle_degrees: f32,
+
+    /// The radius of the character's body.
+    radius: f32,
+
+    /// Visualize the behaviour of this component.
+    ///
+    /// * draws the start and end of the shape that checks for obstacles in front of the feet.
+    /// * draws the shape and ray that check for the top of the step.
+    draw_gizmos: bool,
+}
+
+impl CharacterStepUp {
+    pub fn new(max_step_height: f32, radius: f32, draw_gizmos: bool) -> Self {
+        Self {
+            max_step_height,
+            min_step_height: 0.05,
+            check_distance: 0.15,
+            min_riser_angle_degrees: 60.0,
+            radius,
+            draw_gizmos,
+        }
+    }
+
+    /// The shape that is cast forward from the feet to find the front of a step, and where it starts.
+    ///
+    /// This is a cylinder as wide as the body, between the min and max step height. Its straight sides keep the normals of step edges horizontal, a ball would hit edges at an angle.
+    fn riser_shape(&self, position: Vec3) -> (Collider, Vec3) {
+        let half_height = self.riser_half_height();
+
+        (
+            Collider::cylinder(half_height, self.radius),
+            position + Vec3::Y * (self.min_step_height + half_height),
+        )
+    }
+
+    fn riser_half_height(&self) -> f32 {
+        ((self.max_step_height - self.min_step_height) / 2.0).max(0.01)
+    }
+
+    /// The shape that is cast down onto the top of the step, and where it starts.
+    ///
+    /// This is a thin cylinder that starts just past the front of the step, its flat bottom lands on the step instead of rolling off the edge.
+    fn step_top_shape(&self, riser_position: Vec3, riser_normal: Vec3) -> (Collider, Vec3) {
+        let radius = self.radius / 2.0;
+        let into_step = -Vec3::new(riser_normal.x, 0.0, riser_normal.z).normalize_or_zero();
+
+        (
+            Collider::cylinder(self.step_top_half_height(), radius),
+            riser_position
+                + into_step * (self.radius + radius)
+                + Vec3::Y * (self.step_top_cast_distance() + self.step_top_half_height()),
+        )
+    }
+
+    /// The step top shape is thinner than the lowest step, so it can't start inside a step it should land on.
+    fn step_top_half_height(&self) -> f32 {
+        self.min_step_height / 4.0
+    }
+
+    /// How far the step top shape is cast down, from above the highest step to the feet.
+    fn step_top_cast_distance(&self) -> f32 {
+        self.max_step_height + self.min_step_height
+    }
+
+    fn is_riser(&self, normal: Vec3) -> bool {
+        normal.y.abs() <= (90.0 - self.min_riser_angle_degrees).to_radians().sin()
+    }
+}
+
+/// Characters with a `KinematicCharacterController` are skipped, its autostep already climbs steps.
+#[allow(clippy::type_complexity)]
+fn step_up_character(
+    mut characters: Query<
+        (
+            &mut Velocity,
+            &CharacterStepUp,
+            &Character,
+            &Grounded,
+            &GlobalTransform,
+            Entity,
+        ),
+        Without<KinematicCharacterController>,
+    >,
+    rapier_context: Res<RapierContext>,
+    rapier_config: Res<RapierConfiguration>,
+) {
+    let gravity = rapier_config.gravity.length();
+
+    for (mut velocity, step_up, character, _, global_transform, entity) in characters
+        .iter_mut()
+        .filter(|(_, _, character, grounded, _, _)| character.is_active && grounded.is_grounded())
+    {
+        let Some(direction) = get_horizontal_direction(character.movement_direction) else {
+            continue;
+        };
+
+        let position = global_transform.translation();
+        let filter = QueryFilter::default().exclude_rigid_body(entity);
+
+        let Some(step_height) =
+            find_step_height(&rapier_context, step_up, position, direction, filter)
+        else {
+            continue;
+        };
+
+        // Hop just high enough to land on top of the step, this is smoother than teleporting the character.
+        let lift_velocity = (2.0 * gravity * (step_height + step_up.min_step_height)).sqrt();
+        velocity.linvel.y = velocity.linvel.y.max(lift_velocity);
+    }
+}
+
+/// Returns the height of the step in front of the character relative to its feet, if there is a step it can climb.
+fn find_step_height(
+    rapier_context: &RapierContext,
+    step_up: &CharacterStepUp,
+    position: Vec3,
+    direction: Vec3,
+    filter: QueryFilter,
+) -> Option<f32> {
+    let (riser_shape, riser_origin) = step_up.riser_shape(position);
+    let (_, riser_hit) = rapier_context.cast_shape(
+        riser_origin,
+        Quat::IDENTITY,
+        direction,
+        &riser_shape,
+        ShapeCastOptions::with_max_time_of_impact(step_up.check_distance),
+        filter,
+    )?;
+
+    // The first normal is the outward normal of the collider that was hit, in world space.
+    let riser_normal = riser_hit.details?.normal1;
+
+    if !step_up.is_riser(riser_normal) {
+        return None;
+    }
+
+    let riser_position = position + direction * riser_hit.time_of_impact;
+    let (top_shape, top_origin) = step_up.step_top_shape(riser_position, riser_normal);
+    let (_, top_hit) = rapier_context.cast_shape(
+        top_origin,
+        Quat::IDENTITY,
+        Vec3::NEG_Y,
+        &top_shape,
+        ShapeCastOptions::with_max_time_of_impact(step_up.step_top_cast_distance()),
+        filter,
+    )?;
+
+    // A step top that is too steep is another riser or a slope, not something to stand on.
+    if step_up.is_riser(top_hit.details?.normal1) {
+        return None;
+    }
+
+    let step_height =
+        top_origin.y - step_up.step_top_half_height() - top_hit.time_of_impact - position.y;
+
+    (step_height >= step_up.min_step_height && step_height <= step_up.max_step_height)
+        .then_some(step_height)
+}
+
+fn draw_step_up_gizmos(
+    characters: Query<(&CharacterStepUp, &Character, &GlobalTransform)>,
+    mut gizmos: Gizmos,
+) {
+    for (step_up, character, global_transform) in characters
+        .iter()
+        .filter(|(step_up, _, _)| step_up.draw_gizmos)
+    {
+        let Some(direction) = get_horizontal_direction(character.movement_direction) else {
+            continue;
+        };
+
+        let position = global_transform.translation();
+        let half_height = step_up.riser_half_height();
+        let (_, riser_origin) = step_up.riser_shape(position);
+
+        for height in [-half_height, half_height] {
+            let center = riser_origin + Vec3::Y * height;
+            gizmos.circle(center, Direction3d::Y, step_up.radius, Color::BLUE);
+            gizmos.circle(
+                center + direction * step_up.check_distance,
+                Direction3d::Y,
+                step_up.radius,
+                Color::BLUE,
+            );
+        }
+
+        // Where the step top is checked for a step straight ahead.
+        let (_, top_origin) = step_up.step_top_shape(position, -direction);
+        gizmos.circle(
+            top_origin,
+            Direction3d::Y,
+            step_up.radius / 2.0,
+            Color::BLUE,
+        );
+        gizmos.ray(
+            top_origin,
+            Vec3::NEG_Y * step_up.step_top_cast_distance(),
+            Color::BLUE,
+        );
+    }
+}
+
+// Utilities
+
+fn get_horizontal_direction(direction: Vec3) -> Option<Vec3> {
+    Vec3::new(direction.x, 0.0, direction.z).try_normalize()
+}
@@ -0,0 +1,220 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    grounded::Grounded,
+    wall_contact::{WallContact, WallSide},
+};
+
+use super::{vector_without_y, Character, MovementSet};
+
+pub struct CharacterWallRunPlugin;
+
+impl Plugin for CharacterWallRunPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<WallRunEvent>().add_systems(
+            FixedUpdate,
+            (update_wall_run, apply_wall_run_velocity)
+                .chain()
+                .in_set(MovementSet::WallRun),
+        );
+    }
+}
+
+/// Lets the character run along walls to the left or right of it while in the air.
+///
+/// * NOTE: this entity should also have `Grounded` and `WallContact` components.
+#[derive(Component)]
+pub struct CharacterWallRun {
+    /// The minimum horizontal speed needed to start and keep wall running.
+    pub min_speed: f32,
+
+    /// The max time a single wall run can last, in seconds. After this the character needs to land before wall running again.
+    pub max_duration: f32,
+
+    /// What gravity will be multiplied by while wall running.
+    pub gravity_multiplier: f32,
+
+    /// The max angle between the character's velocity and the wall to start a wall run, in degrees.
+    pub max_approach_angle_degrees: f32,
+
+    /// The angle that a camera can roll away from the wall while wall running, in degrees.
+    pub camera_tilt_degrees: f32,
+
+    state: Option<WallRunState>,
+    can_start: bool,
+}
+
+#[derive(Clone, Copy)]
+struct WallRunState {
+    side: WallSide,
+    wall_normal: Direction3d,
+    elapsed: f32,
+}
+
+impl CharacterWallRun {
+    pub fn new(min_speed: f32, max_duration: f32, gravity_multiplier: f32) -> Self {
+        Self {
+            min_speed,
+            max_duration,
+            gravity_multiplier,
+            max_approach_angle_degrees: 30.0,
+            camera_tilt_degrees: 10.0,
+            state: None,
+            can_start: true,
+        }
+    }
+
+    pub fn is_wall_running(&self) -> bool {
+        self.state.is_some()
+    }
+
+    /// The side of the character that the wall it's running along is on.
+    pub fn wall_side(&self) -> Option<WallSide> {
+        Some(self.state?.side)
+    }
+
+    pub fn wall_normal(&self) -> Option<Direction3d> {
+        Some(self.state?.wall_normal)
+    }
+
+    /// How long the current wall run has lasted, in seconds.
+    pub fn elapsed(&self) -> Option<f32> {
+        Some(self.state?.elapsed)
+    }
+
+    /// Returns the angle in radians that a camera should roll around its forward axis, this tilts it away from the wall.
+    ///
+    /// A positive angle rolls to the left, 0 if the character isn't wall running.
+    pub fn camera_tilt(&self) -> f32 {
+        let tilt = self.camera_tilt_degrees.to_radians();
+
+        match self.wall_side() {
+            Some(WallSide::Right) => tilt,
+            Some(WallSide::Left) => -tilt,
+            _ => 0.0,
+        }
+    }
+
+    /// Returns true if the given horizontal velocity is fast enough and mostly parallel to the wall.
+    fn is_running_along_wall(&self, horizontal_velocity: Vec3, wall_normal: Direction3d) -> bool {
+        let speed = horizontal_velocity.length();
+        let max_approach = self.max_approach_angle_degrees.to_radians().sin();
+
+        speed >= self.min_speed
+            && horizontal_velocity.dot(*wall_normal).abs() <= speed * max_approach
+    }
+}
+
+/// Events sent when a character starts or stops wall running.
+#[derive(Event, Clone, Copy, Debug)]
+pub enum WallRunEvent {
+    Started { entity: Entity, side: WallSide },
+    Ended { entity: Entity },
+}
+
+fn update_wall_run(
+    mut characters: Query<(
+        &mut CharacterWallRun,
+        &Character,
+        &Velocity,
+        &Grounded,
+        &WallContact,
+        Entity,
+    )>,
+    mut wall_run_events: EventWriter<WallRunEvent>,
+    time: Res<Time>,
+) {
+    for (mut wall_run, character, velocity, grounded, wall_contact, entity) in characters.iter_mut()
+    {
+        if grounded.is_grounded() {
+            wall_run.can_start = true;
+        }
+
+        let horizontal_velocity = vector_without_y(velocity.linvel);
+        let side_wall = get_side_wall(wall_contact);
+
+        match wall_run.state {
+            Some(mut state) => {
+                state.elapsed += time.delta_seconds();
+
+                let is_timed_out = state.elapsed >= wall_run.max_duration;
+                let can_continue = character.is_active
+                    && !grounded.is_grounded()
+                    && !is_timed_out
+                    && horizontal_velocity.length() >= wall_run.min_speed;
+
+                // The wall needs to be on the same side and facing the same way, otherwise it's a different wall.
+                let same_wall = side_wall.filter(|(side, wall_normal)| {
+                    *side == state.side && wall_normal.dot(*state.wall_normal) > 0.0
+                });
+
+                match same_wall.filter(|_| can_continue) {
+                    Some((_, wall_normal)) => {
+                        state.wall_normal = wall_normal;
+                        wall_run.state = Some(state);
+                    }
+                    None => {
+                        wall_run.state = None;
+                        wall_run.can_start = !is_timed_out;
+                        wall_run_events.send(WallRunEvent::Ended { entity });
+                    }
+                }
+            }
+            None => {
+                let Some((side, wall_normal)) = side_wall else {
+                    continue;
+                };
+
+                if character.is_active
+                    && wall_run.can_start
+                    && !grounded.is_grounded()
+                    && wall_run.is_running_along_wall(horizontal_velocity, wall_normal)
+                {
+                    wall_run.state = Some(WallRunState {
+                        side,
+                        wall_normal,
+                        elapsed: 0.0,
+                    });
+                    wall_run_events.send(WallRunEvent::Started { entity, side });
+                }
+            }
+        }
+    }
+}
+
+fn apply_wall_run_velocity(
+    mut characters: Query<(&CharacterWallRun, &mut Velocity)>,
+    rapier_config: Res<RapierConfiguration>,
+    time: Res<Time>,
+) {
+    let gravity = rapier_config.gravity.length();
+
+    for (wall_run, mut velocity) in characters.iter_mut() {
+        let Some(state) = wall_run.state else {
+            continue;
+        };
+
+        // Only remove velocity going into the wall, so jumping away from it still works.
+        let into_wall = velocity.linvel.dot(*state.wall_normal).min(0.0);
+        velocity.linvel -= *state.wall_normal * into_wall;
+
+        if state.elapsed == 0.0 {
+            velocity.linvel.y = velocity.linvel.y.max(0.0);
+        }
+
+        velocity.linvel.y += gravity * (1.0 - wall_run.gravity_multiplier) * time.delta_seconds();
+    }
+}
+
+// Utilities
+
+/// Returns the side and normal of the wall the character is touching, if it's to the left or right of the character.
+fn get_side_wall(wall_contact: &WallContact) -> Option<(WallSide, Direction3d)> {
+    let side = wall_contact.wall_side()?;
+
+    match side {
+        WallSide::Left | WallSide::Right => Some((side, wall_contact.wall_normal()?)),
+        WallSide::Front | WallSide::Back => None,
+    }
+}
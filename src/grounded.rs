@@ -1,29 +1,43 @@
 use bevy::prelude::*;
 use bevy_rapier3d::prelude::*;
 
+use crate::character::{Character, CharacterEvent, CharacterSet};
+
 pub struct GroundedPlugin;
 
 impl Plugin for GroundedPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(
-            Update,
-            (
-                update_grounded,
-                draw_grounded_check_gizmos,
-                draw_ground_normal_gizmos,
-            ),
-        );
+        app.add_event::<CharacterEvent>()
+            .configure_sets(
+                FixedUpdate,
+                CharacterSet::GroundCheck.before(PhysicsSet::SyncBackend),
+            )
+            .add_systems(
+                FixedUpdate,
+                update_grounded.in_set(CharacterSet::GroundCheck),
+            )
+            .add_systems(
+                Update,
+                (draw_grounded_check_gizmos, draw_ground_normal_gizmos),
+            );
     }
 }
 
-/// Holds state on if this entity is currently grounded. It checks this by raycasting down.
+/// Holds state on if this entity is currently grounded. It checks this by casting a ray or shape down.
 ///
 /// * NOTE: this entity should also have `rigidbody` and `collider` components.
 #[derive(Component)]
 pub struct Grounded {
-    is_grounded: bool,
+    /// If the entity was grounded at the last check, none before the first check.
+    is_grounded: Option<bool>,
     /// The normal direction of the ground if grounded, or none.
     ground_normal: Option<Direction3d>,
+    /// Where and at what distance the ground was hit if grounded, or none.
+    ground_hit: Option<GroundHit>,
+    /// How long this entity has been in the air, in seconds. 0 while grounded.
+    airtime: f32,
+    /// The downward speed at the last check in the air, used as the impact speed when landing.
+    fall_speed: f32,
     /// The amount that the height of the cast origin will be offsetted, use to finetune position.
     height_offset: f32,
     check_method: CheckMethod,
@@ -38,8 +52,11 @@ pub struct Grounded {
 impl Grounded {
     pub fn new(height_offset: f32, check_method: CheckMethod, draw_gizmos: bool) -> Self {
         Self {
-            is_grounded: false,
+            is_grounded: None,
             ground_normal: None,
+            ground_hit: None,
+            airtime: 0.0,
+            fall_speed: 0.0,
             height_offset,
             check_method,
             draw_gizmos,
@@ -47,22 +64,144 @@ impl Grounded {
     }
 
     pub fn is_grounded(&self) -> bool {
-        self.is_grounded
+        self.is_grounded.unwrap_or(false)
     }
 
     pub fn ground_normal(&self) -> Option<Direction3d> {
         self.ground_normal
     }
 
+    /// Returns how long this entity has been in the air in seconds, 0 while grounded.
+    pub fn airtime(&self) -> f32 {
+        self.airtime
+    }
+
+    /// Returns the entity this entity is standing on.
+    pub fn ground_entity(&self) -> Option<Entity> {
+        Some(self.ground_hit?.entity)
+    }
+
+    /// Returns the point where the ground was hit, in world space.
+    pub fn ground_point(&self) -> Option<Vec3> {
+        Some(self.ground_hit?.point)
+    }
+
+    /// Returns how far the check traveled down from its origin before hitting the ground.
+    pub fn ground_distance(&self) -> Option<f32> {
+        Some(self.ground_hit?.distance)
+    }
+
+    /// Returns true if only part of the ground check hit the ground, like when standing on a ledge.
+    ///
+    /// Only the `RayFan` check method can be partially grounded.
+    pub fn is_on_edge(&self) -> bool {
+        self.ground_hit.is_some_and(|hit| hit.coverage < 1.0)
+    }
+
     pub fn ground_rotation(&self) -> Option<Quat> {
         Some(ground_normal_as_rotation(self.ground_normal?))
     }
+
+    /// Returns the angle between the ground and a flat surface in degrees, or none if not grounded.
+    pub fn slope_angle_degrees(&self) -> Option<f32> {
+        Some(self.ground_normal?.angle_between(Vec3::Y).to_degrees())
+    }
+
+    /// Returns the direction pointing down the slope along the ground, or none if not grounded or the ground is flat.
+    pub fn downhill_direction(&self) -> Option<Vec3> {
+        let normal = *self.ground_normal?;
+
+        (Vec3::NEG_Y - normal * normal.dot(Vec3::NEG_Y)).try_normalize()
+    }
 }
 
+/// The ray or shape that is cast down to check if an entity is grounded.
+///
+/// The `distance` of each method is how far down it is cast from the origin.
 #[derive(Clone, Copy)]
 pub enum CheckMethod {
-    Ray { distance: f32 },
-    Sphere { radius: f32 },
+    Ray {
+        distance: f32,
+    },
+    Sphere {
+        radius: f32,
+        distance: f32,
+    },
+    /// A vertical capsule, `half_height` is half the length of the straight middle section.
+    Capsule {
+        radius: f32,
+        half_height: f32,
+        distance: f32,
+    },
+    /// A vertical cylinder.
+    Cylinder {
+        radius: f32,
+        half_height: f32,
+        distance: f32,
+    },
+    Box {
+        half_extents: Vec3,
+        distance: f32,
+    },
+    /// A ray down from the center, plus `ray_count` rays spread evenly around a circle with the given radius.
+    ///
+    /// The results are combined into an averaged ground normal and the closest hit, this is more accurate on uneven ground and ledges.
+    RayFan {
+        radius: f32,
+        ray_count: u32,
+        distance: f32,
+    },
+}
+
+impl CheckMethod {
+    pub fn distance(&self) -> f32 {
+        match *self {
+            CheckMethod::Ray { distance }
+            | CheckMethod::Sphere { distance, .. }
+            | CheckMethod::Capsule { distance, .. }
+            | CheckMethod::Cylinder { distance, .. }
+            | CheckMethod::Box { distance, .. }
+            | CheckMethod::RayFan { distance, .. } => distance,
+        }
+    }
+
+    /// Returns the collider shape that is cast, or none for rays.
+    fn shape(&self) -> Option<Collider> {
+        match *self {
+            CheckMethod::Ray { .. } | CheckMethod::RayFan { .. } => None,
+            CheckMethod::Sphere { radius, .. } => Some(Collider::ball(radius)),
+            CheckMethod::Capsule {
+                radius,
+                half_height,
+                ..
+            } => Some(Collider::capsule_y(half_height, radius)),
+            CheckMethod::Cylinder {
+                radius,
+                half_height,
+                ..
+            } => Some(Collider::cylinder(half_height, radius)),
+            CheckMethod::Box { half_extents, .. } => Some(Collider::cuboid(
+                half_extents.x,
+                half_extents.y,
+                half_extents.z,
+            )),
+        }
+    }
+}
+
+/// The result of a grounded check that hit something.
+#[derive(Clone, Copy, Debug)]
+pub struct GroundHit {
+    /// The rigid body entity that was hit, or the collider entity if it isn't attached to a rigid body.
+    pub entity: Entity,
+    /// The point where the ground was hit, in world space.
+    pub point: Vec3,
+    /// The normal direction of the ground at the hit point.
+    pub normal: Direction3d,
+    /// How far the ray or shape traveled before hitting the ground.
+    pub distance: f32,
+    /// The fraction of rays that hit the ground, from 0 to 1. This is always 1 for methods that only cast once.
+    pub coverage: f32,
 }
 
 struct CastInfo {
@@ -84,13 +223,44 @@ impl CastInfo {
             method,
         }
     }
+
+    /// Returns the position the ray or shape would end up at if it didn't hit anything.
+    fn end(&self) -> Vec3 {
+        self.origin + self.direction * self.method.distance()
+    }
+
+    /// Returns the origins of all rays that a `RayFan` casts, the first one is the center.
+    fn fan_origins(&self, radius: f32, ray_count: u32) -> Vec<Vec3> {
+        let around_circle = (0..ray_count).map(|i| {
+            let angle = std::f32::consts::TAU * i as f32 / ray_count as f32;
+            self.origin + Quat::from_rotation_y(angle) * Vec3::X * radius
+        });
+
+        std::iter::once(self.origin).chain(around_circle).collect()
+    }
 }
 
+#[allow(clippy::type_complexity)]
 fn update_grounded(
-    mut grounded_components: Query<(&mut Grounded, &GlobalTransform, Entity)>,
+    mut grounded_components: Query<(
+        &mut Grounded,
+        &GlobalTransform,
+        Option<&Velocity>,
+        Has<Character>,
+        Entity,
+    )>,
+    mut character_events: EventWriter<CharacterEvent>,
     rapier_context: Res<RapierContext>,
+    time: Res<Time>,
 ) {
-    for (mut grounded, global_transform, entity) in grounded_components.iter_mut() {
+    for (mut grounded, global_transform, velocity, is_character, entity) in
+        grounded_components.iter_mut()
+    {
+        // The ground check runs before rapier adds new bodies, so a character spawned this step would always miss the ground.
+        if !rapier_context.entity2body().contains_key(&entity) {
+            continue;
+        }
+
         let cast_info = CastInfo::from_translation(
             global_transform.translation(),
             Vec3::NEG_Y,
@@ -99,64 +269,144 @@ fn update_grounded(
         );
         let filter = QueryFilter::default().exclude_rigid_body(entity);
 
-        let cast_result = get_normal_from_cast(&rapier_context, &cast_info, filter);
+        let ground_hit = get_ground_hit(&rapier_context, &cast_info, filter);
+        let was_grounded = grounded.is_grounded;
 
-        grounded.is_grounded = cast_result.is_some();
-        grounded.ground_normal = cast_result.and_then(|result| result.try_into().ok());
+        grounded.is_grounded = Some(ground_hit.is_some());
+        grounded.ground_normal = ground_hit.map(|hit| hit.normal);
+        grounded.ground_hit = ground_hit;
+
+        // There is no transition on the first check after rapier knows the body, a character that spawns on the ground didn't land.
+        let event = match (was_grounded, grounded.is_grounded()) {
+            (Some(true), false) => Some(CharacterEvent::LeftGround { entity }),
+            (Some(false), true) => Some(CharacterEvent::Landed {
+                entity,
+                impact_speed: grounded.fall_speed,
+                airtime: grounded.airtime,
+            }),
+            _ => None,
+        };
+
+        if let Some(event) = event.filter(|_| is_character) {
+            character_events.send(event);
+        }
+
+        match grounded.is_grounded() {
+            true => {
+                grounded.airtime = 0.0;
+                grounded.fall_speed = 0.0;
+            }
+            false => {
+                let downward_speed = velocity.map_or(0.0, |velocity| -velocity.linvel.y);
+
+                grounded.airtime += time.delta_seconds();
+                grounded.fall_speed = downward_speed;
+            }
+        }
     }
 }
 
-fn get_normal_from_cast(
+fn get_ground_hit(
     rapier_context: &RapierContext,
     cast_info: &CastInfo,
     filter: QueryFilter,
-) -> Option<Vec3> {
-    match cast_info.method {
-        CheckMethod::Ray { distance } => {
-            check_ray_hit_normal(&rapier_context, cast_info, distance, filter)
-        }
-        CheckMethod::Sphere { radius } => {
-            if check_sphere_hit(&rapier_context, cast_info, radius, filter) {
-                // This way of getting the normal & hard setting the distance to 10 is not ideal, but I don't expect it to cause problems.
-                check_ray_hit_normal(rapier_context, cast_info, 10.0, filter)
-            } else {
-                None
-            }
-        }
+) -> Option<GroundHit> {
+    if let CheckMethod::RayFan {
+        radius, ray_count, ..
+    } = cast_info.method
+    {
+        return cast_ray_fan(rapier_context, cast_info, radius, ray_count, filter);
+    }
+
+    match cast_info.method.shape() {
+        Some(shape) => cast_shape(rapier_context, cast_info, &shape, filter),
+        None => cast_ray(rapier_context, cast_info.origin, cast_info, filter),
     }
 }
 
-fn check_ray_hit_normal(
+fn cast_ray(
     rapier_context: &RapierContext,
+    origin: Vec3,
     cast_info: &CastInfo,
-    distance: f32,
     filter: QueryFilter,
-) -> Option<Vec3> {
-    rapier_context
-        .cast_ray_and_get_normal(
-            cast_info.origin,
-            cast_info.direction,
-            distance,
-            true,
-            filter,
-        )
-        .and_then(|hit| Some(hit.1.normal))
+) -> Option<GroundHit> {
+    let (collider_entity, hit) = rapier_context.cast_ray_and_get_normal(
+        origin,
+        cast_info.direction,
+        cast_info.method.distance(),
+        true,
+        filter,
+    )?;
+
+    Some(GroundHit {
+        entity: get_body_entity(rapier_context, collider_entity),
+        point: hit.point,
+        normal: hit.normal.try_into().ok()?,
+        distance: hit.point.distance(origin),
+        coverage: 1.0,
+    })
 }
 
-fn check_sphere_hit(
+/// Casts all rays of a `RayFan` and combines them into a single hit with the averaged normal and the closest point.
+fn cast_ray_fan(
     rapier_context: &RapierContext,
     cast_info: &CastInfo,
     radius: f32,
+    ray_count: u32,
     filter: QueryFilter,
-) -> bool {
+) -> Option<GroundHit> {
+    let origins = cast_info.fan_origins(radius, ray_count);
+    let hits: Vec<GroundHit> = origins
+        .iter()
+        .filter_map(|origin| cast_ray(rapier_context, *origin, cast_info, filter))
+        .collect();
+
+    let closest_hit = hits
+        .iter()
+        .min_by(|a, b| a.distance.total_cmp(&b.distance))?;
+    let normal_sum: Vec3 = hits.iter().map(|hit| *hit.normal).sum();
+
+    Some(GroundHit {
+        entity: closest_hit.entity,
+        point: closest_hit.point,
+        normal: normal_sum.try_into().ok()?,
+        distance: closest_hit.distance,
+        coverage: hits.len() as f32 / origins.len() as f32,
+    })
+}
+
+fn cast_shape(
+    rapier_context: &RapierContext,
+    cast_info: &CastInfo,
+    shape: &Collider,
+    filter: QueryFilter,
+) -> Option<GroundHit> {
+    let (collider_entity, hit) = rapier_context.cast_shape(
+        cast_info.origin,
+        Quat::IDENTITY,
+        cast_info.direction,
+        shape,
+        ShapeCastOptions::with_max_time_of_impact(cast_info.method.distance()),
+        filter,
+    )?;
+
+    // The first witness point and normal are on the collider that was hit, in world space.
+    let details = hit.details?;
+
+    Some(GroundHit {
+        entity: get_body_entity(rapier_context, collider_entity),
+        point: details.witness1,
+        normal: details.normal1.try_into().ok()?,
+        distance: hit.time_of_impact,
+        coverage: 1.0,
+    })
+}
+
+/// Returns the rigid body entity that the collider is attached to, or the collider entity itself if it has no rigid body.
+fn get_body_entity(rapier_context: &RapierContext, collider_entity: Entity) -> Entity {
     rapier_context
-        .intersection_with_shape(
-            cast_info.origin,
-            Quat::IDENTITY,
-            &Collider::ball(radius),
-            filter,
-        )
-        .is_some()
+        .collider_parent(collider_entity)
+        .unwrap_or(collider_entity)
 }
 
 fn draw_grounded_check_gizmos(
@@ -173,19 +423,78 @@ fn draw_grounded_check_gizmos(
             grounded.height_offset,
             grounded.check_method,
         );
+        let end = cast_info.end();
+
+        gizmos.line(cast_info.origin, end, Color::BLUE);
 
         match cast_info.method {
-            CheckMethod::Ray { distance } => {
-                gizmos.ray(
-                    cast_info.origin,
-                    cast_info.direction * distance,
-                    Color::BLUE,
-                );
+            CheckMethod::Ray { .. } => (),
+            CheckMethod::RayFan {
+                radius,
+                ray_count,
+                distance,
+            } => {
+                for origin in cast_info.fan_origins(radius, ray_count) {
+                    gizmos.ray(origin, cast_info.direction * distance, Color::BLUE);
+                }
             }
-            CheckMethod::Sphere { radius } => {
+            CheckMethod::Sphere { radius, .. } => {
                 gizmos.sphere(cast_info.origin, Quat::IDENTITY, radius, Color::BLUE);
+                gizmos.sphere(end, Quat::IDENTITY, radius, Color::BLUE);
+            }
+            CheckMethod::Capsule {
+                radius,
+                half_height,
+                ..
+            } => {
+                for center in [cast_info.origin, end] {
+                    gizmos.sphere(
+                        center + Vec3::Y * half_height,
+                        Quat::IDENTITY,
+                        radius,
+                        Color::BLUE,
+                    );
+                    gizmos.sphere(
+                        center - Vec3::Y * half_height,
+                        Quat::IDENTITY,
+                        radius,
+                        Color::BLUE,
+                    );
+                }
+            }
+            CheckMethod::Cylinder {
+                radius,
+                half_height,
+                ..
+            } => {
+                for center in [cast_info.origin, end] {
+                    gizmos.circle(
+                        center + Vec3::Y * half_height,
+                        Direction3d::Y,
+                        radius,
+                        Color::BLUE,
+                    );
+                    gizmos.circle(
+                        center - Vec3::Y * half_height,
+                        Direction3d::Y,
+                        radius,
+                        Color::BLUE,
+                    );
+                }
+            }
+            CheckMethod::Box { half_extents, .. } => {
+                for center in [cast_info.origin, end] {
+                    gizmos.cuboid(
+                        Transform::from_translation(center).with_scale(half_extents * 2.0),
+                        Color::BLUE,
+                    );
+                }
             }
         };
+
+        if let Some(hit) = grounded.ground_hit {
+            gizmos.sphere(hit.point, Quat::IDENTITY, 0.05, Color::LIME_GREEN);
+        }
     }
 }
 
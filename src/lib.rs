@@ -1,3 +1,4 @@
 pub mod character;
 pub mod grounded;
 pub mod player_movement_input;
+pub mod wall_contact;
@@ -14,6 +14,7 @@ use character_controller_1::{
     },
     grounded::GroundedPlugin,
     player_movement_input::{PlayerMovementInput, PlayerMovementInputPlugin},
+    wall_contact::WallContactPlugin,
 };
 use world::WorldPlugin;
 
@@ -21,11 +22,12 @@ fn main() {
     App::new()
         .add_plugins((
             DefaultPlugins,
-            RapierPhysicsPlugin::<NoUserData>::default(),
+            RapierPhysicsPlugin::<NoUserData>::default().in_fixed_schedule(),
             // RapierDebugRenderPlugin::default(),
             CharacterPlugin,
             PlayerMovementInputPlugin,
             GroundedPlugin,
+            WallContactPlugin,
             WorldPlugin,
         ))
         .add_systems(Startup, spawn_test_character)
@@ -63,6 +65,14 @@ fn spawn_test_character(
         .spawn_core(&mut commands, character, character_config)
         .add_body(&mut commands, &mut meshes, &mut materials)
         .add_jumping(&mut commands)
+        .add_wall_contact(&mut commands)
+        .add_wall_running(&mut commands)
+        .add_step_up(&mut commands)
+        .add_platform_carry(&mut commands)
+        .add_stances(&mut commands)
+        .add_sliding(&mut commands)
+        .add_stamina(&mut commands)
+        .add_dashing(&mut commands)
         // .add_camera(&mut commands, build_first_person_camera())
         .add_camera(&mut commands, build_third_person_camera(7.0))
         .add_root_component(&mut commands, movement_input);
@@ -1,8 +1,8 @@
 use bevy::{input::mouse::MouseMotion, prelude::*};
 
-use crate::{
-    character::{crouch::CharacterCrouch, jump::CharacterJump, Character},
-    grounded::Grounded,
+use crate::character::{
+    dash::CharacterDash, jump::CharacterJump, stamina::CharacterStamina, stance::CharacterStance,
+    Character, CharacterSet,
 };
 
 pub struct PlayerMovementInputPlugin;
@@ -13,11 +13,14 @@ impl Plugin for PlayerMovementInputPlugin {
             Update,
             (
                 update_character_movement_input,
-                update_character_rotation_input,
+                update_character_look_input,
                 update_character_running,
                 update_character_jump_input,
                 update_character_crouch_input,
-            ),
+                update_character_prone_input,
+                update_character_dash_input,
+            )
+                .in_set(CharacterSet::Input),
         );
     }
 }
@@ -27,6 +30,7 @@ pub struct PlayerMovementInput {
     pub keybinds: MovementKeybinds,
     pub hold_to_run: bool,
     pub hold_to_crouch: bool,
+    pub hold_to_prone: bool,
 }
 
 impl Default for PlayerMovementInput {
@@ -35,6 +39,7 @@ impl Default for PlayerMovementInput {
             keybinds: MovementKeybinds::default(),
             hold_to_run: true,
             hold_to_crouch: true,
+            hold_to_prone: false,
         }
     }
 }
@@ -47,6 +52,8 @@ pub struct MovementKeybinds {
     pub run_key: KeyCode,
     pub jump_key: KeyCode,
     pub crouch_key: KeyCode,
+    pub prone_key: KeyCode,
+    pub dash_key: KeyCode,
 }
 
 impl Default for MovementKeybinds {
@@ -59,6 +66,8 @@ impl Default for MovementKeybinds {
             run_key: KeyCode::ShiftLeft,
             jump_key: KeyCode::Space,
             crouch_key: KeyCode::ControlLeft,
+            prone_key: KeyCode::KeyZ,
+            dash_key: KeyCode::KeyQ,
         }
     }
 }
@@ -74,7 +83,7 @@ fn update_character_movement_input(
     }
 }
 
-fn update_character_rotation_input(
+fn update_character_look_input(
     mut characters: Query<&mut Character, With<PlayerMovementInput>>,
     mut mouse_motion: EventReader<MouseMotion>,
 ) {
@@ -82,24 +91,33 @@ fn update_character_rotation_input(
         .read()
         .fold(Vec2::ZERO, |sum, motion| sum + motion.delta);
 
-    let as_rotation = Vec3::new(-sum.y, -sum.x, 0.0);
+    // Screen Y points down, the character looks up with positive Y.
+    let look_delta = Vec2::new(sum.x, -sum.y);
 
+    // Added up instead of overwritten so other input sources can add to it too, the rotation systems reset it every frame.
     for mut character in characters.iter_mut() {
-        character.rotation_input = as_rotation;
+        character.look_delta += look_delta;
     }
 }
 
 fn update_character_running(
-    mut characters: Query<(&PlayerMovementInput, &mut Character)>,
+    mut characters: Query<(
+        &PlayerMovementInput,
+        &mut Character,
+        Option<&CharacterStamina>,
+    )>,
     input: Res<ButtonInput<KeyCode>>,
 ) {
-    for (movement, mut character) in characters.iter_mut() {
+    for (movement, mut character, stamina) in characters.iter_mut() {
+        // An exhausted character can't start running, the stamina component stops it if it already is.
+        let can_run = !stamina.is_some_and(|stamina| stamina.is_exhausted());
+
         if movement.hold_to_run {
-            if input.pressed(movement.keybinds.run_key) != character.is_running {
+            if (input.pressed(movement.keybinds.run_key) && can_run) != character.is_running {
                 character.toggle_running();
             }
         } else {
-            if input.just_pressed(movement.keybinds.run_key) {
+            if input.just_pressed(movement.keybinds.run_key) && (character.is_running || can_run) {
                 character.toggle_running();
             }
         }
@@ -107,33 +125,61 @@ fn update_character_running(
 }
 
 fn update_character_jump_input(
-    mut characters: Query<(&PlayerMovementInput, &mut CharacterJump, &Grounded)>,
+    mut characters: Query<(&PlayerMovementInput, &mut CharacterJump)>,
     input: Res<ButtonInput<KeyCode>>,
 ) {
-    for (movement, mut jump, grounded) in characters.iter_mut() {
-        if input.just_pressed(movement.keybinds.jump_key) && grounded.is_grounded() {
+    for (movement, mut jump) in characters.iter_mut() {
+        if input.just_pressed(movement.keybinds.jump_key) {
             jump.has_jump_input = true;
         }
+
+        jump.is_jump_held = input.pressed(movement.keybinds.jump_key);
     }
 }
 
 fn update_character_crouch_input(
-    mut characters: Query<(&PlayerMovementInput, &mut CharacterCrouch)>,
+    mut characters: Query<(&PlayerMovementInput, &mut CharacterStance)>,
     input: Res<ButtonInput<KeyCode>>,
 ) {
-    for (movement, mut crouch) in characters.iter_mut() {
+    for (movement, mut stance) in characters.iter_mut() {
         if movement.hold_to_crouch {
-            if input.pressed(movement.keybinds.crouch_key) != crouch.has_crouch_input {
-                crouch.toggle_crouch_input();
+            if input.pressed(movement.keybinds.crouch_key) != stance.has_crouch_input {
+                stance.toggle_crouch_input();
             }
         } else {
             if input.just_pressed(movement.keybinds.crouch_key) {
-                crouch.toggle_crouch_input();
+                stance.toggle_crouch_input();
             }
         }
     }
 }
 
+fn update_character_prone_input(
+    mut characters: Query<(&PlayerMovementInput, &mut CharacterStance)>,
+    input: Res<ButtonInput<KeyCode>>,
+) {
+    for (movement, mut stance) in characters.iter_mut() {
+        if movement.hold_to_prone {
+            if input.pressed(movement.keybinds.prone_key) != stance.has_prone_input {
+                stance.toggle_prone_input();
+            }
+        } else if input.just_pressed(movement.keybinds.prone_key) {
+            stance.toggle_prone_input();
+        }
+    }
+}
+
+fn update_character_dash_input(
+    mut characters: Query<(&PlayerMovementInput, &mut CharacterDash)>,
+    input: Res<ButtonInput<KeyCode>>,
+) {
+    for (movement, mut dash) in characters.iter_mut() {
+        if input.just_pressed(movement.keybinds.dash_key) {
+            dash.has_dash_input = true;
+        }
+    }
+}
+
 fn walk_direction_from_input(
     keybinds: &MovementKeybinds,
     input: &Res<ButtonInput<KeyCode>>,
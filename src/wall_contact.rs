@@ -0,0 +1,197 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::character::CharacterSet;
+
+pub struct WallContactPlugin;
+
+impl Plugin for WallContactPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            update_wall_contact.in_set(CharacterSet::GroundCheck),
+        )
+        .add_systems(Update, draw_wall_contact_gizmos);
+    }
+}
+
+/// Holds state on if this entity is currently touching a wall. It checks this by casting a sphere to each side.
+///
+/// * NOTE: this entity should also have `rigidbody` and `collider` components.
+#[derive(Component)]
+pub struct WallContact {
+    /// The normal direction of the closest wall if touching one, or none.
+    wall_normal: Option<Direction3d>,
+    /// The side of this entity that the closest wall is on, or none.
+    wall_side: Option<WallSide>,
+    /// The height above the entity's origin that the spheres are cast from.
+    height_offset: f32,
+    /// The radius of the spheres that are cast.
+    radius: f32,
+    /// How far the spheres are cast.
+    distance: f32,
+    /// The max angle in degrees that a surface can be tilted away from vertical to still count as a wall.
+    max_wall_tilt_degrees: f32,
+
+    /// Visualize the behaviour of this component.
+    ///
+    /// * draws the rays along which the spheres are cast.
+    /// * draws the normal direction of the wall if this entity is touching one.
+    draw_gizmos: bool,
+}
+
+impl WallContact {
+    pub fn new(
+        height_offset: f32,
+        radius: f32,
+        distance: f32,
+        max_wall_tilt_degrees: f32,
+        draw_gizmos: bool,
+    ) -> Self {
+        Self {
+            wall_normal: None,
+            wall_side: None,
+            height_offset,
+            radius,
+            distance,
+            max_wall_tilt_degrees,
+            draw_gizmos,
+        }
+    }
+
+    pub fn is_touching_wall(&self) -> bool {
+        self.wall_normal.is_some()
+    }
+
+    pub fn wall_normal(&self) -> Option<Direction3d> {
+        self.wall_normal
+    }
+
+    pub fn wall_side(&self) -> Option<WallSide> {
+        self.wall_side
+    }
+
+    fn is_wall(&self, normal: Vec3) -> bool {
+        normal.y.abs() <= self.max_wall_tilt_degrees.to_radians().sin()
+    }
+}
+
+/// The side of an entity relative to its own rotation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WallSide {
+    Left,
+    Right,
+    Front,
+    Back,
+}
+
+impl WallSide {
+    const ALL: [WallSide; 4] = [
+        WallSide::Left,
+        WallSide::Right,
+        WallSide::Front,
+        WallSide::Back,
+    ];
+
+    /// Returns the direction of this side in world space.
+    fn direction(&self, global_transform: &GlobalTransform) -> Vec3 {
+        match self {
+            WallSide::Left => global_transform.left(),
+            WallSide::Right => global_transform.right(),
+            WallSide::Front => global_transform.forward(),
+            WallSide::Back => global_transform.back(),
+        }
+    }
+}
+
+struct WallHit {
+    side: WallSide,
+    normal: Vec3,
+    distance: f32,
+}
+
+fn update_wall_contact(
+    mut wall_contacts: Query<(&mut WallContact, &GlobalTransform, Entity)>,
+    rapier_context: Res<RapierContext>,
+) {
+    for (mut wall_contact, global_transform, entity) in wall_contacts.iter_mut() {
+        let origin = global_transform.translation() + Vec3::Y * wall_contact.height_offset;
+        let shape = Collider::ball(wall_contact.radius);
+        let filter = QueryFilter::default().exclude_rigid_body(entity);
+
+        let closest_hit = WallSide::ALL
+            .iter()
+            .filter_map(|side| {
+                cast_towards_side(
+                    &rapier_context,
+                    &wall_contact,
+                    origin,
+                    &shape,
+                    *side,
+                    global_transform,
+                    filter,
+                )
+            })
+            .min_by(|a, b| a.distance.total_cmp(&b.distance));
+
+        wall_contact.wall_side = closest_hit.as_ref().map(|hit| hit.side);
+        wall_contact.wall_normal = closest_hit.and_then(|hit| hit.normal.try_into().ok());
+    }
+}
+
+fn cast_towards_side(
+    rapier_context: &RapierContext,
+    wall_contact: &WallContact,
+    origin: Vec3,
+    shape: &Collider,
+    side: WallSide,
+    global_transform: &GlobalTransform,
+    filter: QueryFilter,
+) -> Option<WallHit> {
+    let (_, hit) = rapier_context.cast_shape(
+        origin,
+        Quat::IDENTITY,
+        side.direction(global_transform),
+        shape,
+        ShapeCastOptions::with_max_time_of_impact(wall_contact.distance),
+        filter,
+    )?;
+
+    // The first normal is the outward normal of the collider that was hit, in world space.
+    let normal = hit.details?.normal1;
+
+    wall_contact.is_wall(normal).then_some(WallHit {
+        side,
+        normal,
+        distance: hit.time_of_impact,
+    })
+}
+
+fn draw_wall_contact_gizmos(
+    wall_contacts: Query<(&WallContact, &GlobalTransform)>,
+    mut gizmos: Gizmos,
+) {
+    for (wall_contact, global_transform) in wall_contacts
+        .iter()
+        .filter(|(wall_contact, _)| wall_contact.draw_gizmos)
+    {
+        let origin = global_transform.translation() + Vec3::Y * wall_contact.height_offset;
+
+        for side in WallSide::ALL {
+            let color = match wall_contact.wall_side == Some(side) {
+                true => Color::ORANGE,
+                false => Color::BLUE,
+            };
+
+            gizmos.ray(
+                origin,
+                side.direction(global_transform) * (wall_contact.radius + wall_contact.distance),
+                color,
+            );
+        }
+
+        if let Some(normal) = wall_contact.wall_normal {
+            gizmos.ray(origin, normal * 1.5, Color::ORANGE);
+        }
+    }
+}
@@ -15,8 +15,12 @@ impl Plugin for WorldPlugin {
                 spawn_cubes,
                 spawn_slopes,
                 spawn_sphere,
+                spawn_stairs,
+                spawn_moving_platforms,
+                spawn_tunnel,
             ),
-        );
+        )
+        .add_systems(FixedUpdate, move_platforms.before(PhysicsSet::SyncBackend));
     }
 }
 
@@ -161,6 +165,164 @@ fn spawn_sphere(
     ));
 }
 
+fn spawn_stairs(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let base_position = Vec3::new(-10.0, 0.0, 0.0);
+    let step_count = 8;
+    let step_height = 0.2;
+    let step_depth = 0.35;
+    let width = 2.5;
+
+    let material_handle = build_material(&mut materials, Color::SALMON);
+
+    for i in 0..step_count {
+        // Each step reaches down to the ground so the staircase is solid.
+        let height = step_height * (i + 1) as f32;
+        let size = Vec3::new(width, height, step_depth);
+        let spawn_position = base_position + Vec3::new(0.0, height / 2.0, -step_depth * i as f32);
+
+        commands.spawn((
+            Name::from(format!("Stair step {}", i + 1)),
+            build_cube(
+                spawn_position,
+                Quat::IDENTITY,
+                size,
+                build_rectangle_mesh(&mut meshes, size),
+                material_handle.clone(),
+            ),
+        ));
+    }
+}
+
+fn spawn_tunnel(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let base_position = Vec3::new(4.0, 0.0, -10.0);
+    let length = 6.0;
+    let width = 2.0;
+    let height = 1.5;
+    let thickness = 0.3;
+
+    let material_handle = build_material(&mut materials, Color::OLIVE);
+
+    // Low enough that a standing character doesn't fit, but a crouching one does.
+    let wall_size = Vec3::new(thickness, height, length);
+    let roof_size = Vec3::new(width + thickness * 2.0, thickness, length);
+    let wall_offset = (width + thickness) / 2.0;
+
+    let parts = [
+        (
+            "Tunnel left wall",
+            Vec3::new(-wall_offset, height / 2.0, 0.0),
+            wall_size,
+        ),
+        (
+            "Tunnel right wall",
+            Vec3::new(wall_offset, height / 2.0, 0.0),
+            wall_size,
+        ),
+        (
+            "Tunnel roof",
+            Vec3::new(0.0, height + thickness / 2.0, 0.0),
+            roof_size,
+        ),
+    ];
+
+    for (name, offset, size) in parts {
+        commands.spawn((
+            Name::from(name),
+            build_cube(
+                base_position + offset,
+                Quat::IDENTITY,
+                size,
+                build_rectangle_mesh(&mut meshes, size),
+                material_handle.clone(),
+            ),
+        ));
+    }
+}
+
+/// A kinematic platform that moves back and forth between two points.
+#[derive(Component)]
+struct MovingPlatform {
+    start: Vec3,
+    end: Vec3,
+    speed: f32,
+    moving_to_end: bool,
+}
+
+fn spawn_moving_platforms(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let material_handle = build_material(&mut materials, Color::ORANGE);
+
+    // Moving platform
+    let start = Vec3::new(-4.0, 0.5, 10.0);
+    let size = Vec3::new(3.0, 0.4, 3.0);
+
+    commands.spawn((
+        Name::from("Moving platform"),
+        build_cube(
+            start,
+            Quat::IDENTITY,
+            size,
+            build_rectangle_mesh(&mut meshes, size),
+            material_handle.clone(),
+        ),
+        RigidBody::KinematicVelocityBased,
+        Velocity::default(),
+        MovingPlatform {
+            start,
+            end: Vec3::new(6.0, 3.0, 10.0),
+            speed: 2.0,
+            moving_to_end: true,
+        },
+    ));
+
+    // Rotating disc
+    let radius = 3.0;
+    let height = 0.4;
+
+    commands.spawn((
+        Name::from("Rotating disc"),
+        PbrBundle {
+            mesh: meshes.add(Cylinder::new(radius, height)),
+            material: material_handle,
+            transform: Transform::from_xyz(-12.0, height / 2.0, 10.0),
+            ..default()
+        },
+        Collider::cylinder(height / 2.0, radius),
+        RigidBody::KinematicVelocityBased,
+        Velocity::angular(Vec3::Y * 0.8),
+    ));
+}
+
+fn move_platforms(
+    mut platforms: Query<(&mut MovingPlatform, &mut Velocity, &Transform)>,
+    time: Res<Time>,
+) {
+    for (mut platform, mut velocity, transform) in platforms.iter_mut() {
+        let target = match platform.moving_to_end {
+            true => platform.end,
+            false => platform.start,
+        };
+        let to_target = target - transform.translation;
+
+        if to_target.length() <= platform.speed * time.delta_seconds() {
+            platform.moving_to_end = !platform.moving_to_end;
+        }
+
+        velocity.linvel = to_target.normalize_or_zero() * platform.speed;
+    }
+}
+
 fn build_cube_mesh(meshes: &mut ResMut<Assets<Mesh>>, size: f32) -> Handle<Mesh> {
     meshes.add(Cuboid::from_size(Vec3::splat(size)))
 }